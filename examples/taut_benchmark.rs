@@ -0,0 +1,321 @@
+//! Measures how much keeping paths taut saves, on a few generated scenes:
+//!
+//! - The navigation graph only keeps edges that are tangent to the obstacles at both ends. It is
+//!   compared to the full visibility graph between all obstacle vertices.
+//! - `FindPathOptions::taut_successors` additionally skips the neighbors a path can not turn to,
+//!   given the edge it arrived on.
+//!
+//! Whole queries are dominated by connecting the end points to the graph, so the search alone is
+//! timed as well.
+//!
+//! Run with `cargo run --release --example taut_benchmark`.
+
+use std::time::Instant;
+
+use noisy_float::prelude::*;
+
+use non_grid_path_finder::a_star::{a_star, AStarBudget, AStarInput};
+use non_grid_path_finder::geometry::{orient2d, path_length, Segment, Shape, Sign, Vec2};
+use non_grid_path_finder::navigation::{
+    FindPathOptions, Navigation, NavigationInternal, NavigationObstacle, PathSearchStatus,
+};
+
+const SIZE: f64 = 1000.;
+const QUERIES: usize = 200;
+const SEARCH_ROUNDS: usize = 20;
+
+/// A linear congruential generator, so that every run uses the same scenes.
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> f64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+    fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + self.next() * (max - min)
+    }
+}
+
+fn regular_polygon(center: Vec2, radius: f64, sides: usize, rotation: f64) -> Vec<Vec2> {
+    (0..sides)
+        .map(|i| {
+            let angle = rotation + i as f64 / sides as f64 * std::f64::consts::PI * 2.;
+            center + Vec2::new(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
+
+/// Convex polygons of different sizes that do not overlap.
+fn scattered_polygons(random: &mut Random) -> Vec<NavigationObstacle> {
+    let mut circles: Vec<(Vec2, f64)> = vec![];
+    while circles.len() < 120 {
+        let center = Vec2::new(random.range(0., SIZE), random.range(0., SIZE));
+        let radius = random.range(10., 40.);
+        if circles
+            .iter()
+            .all(|&(other_center, other_radius)| center.dist(other_center) > radius + other_radius + 5.)
+        {
+            circles.push((center, radius));
+        }
+    }
+    circles
+        .into_iter()
+        .map(|(center, radius)| {
+            let sides = 3 + (random.next() * 6.) as usize;
+            NavigationObstacle::new(regular_polygon(center, radius, sides, random.range(0., 7.)))
+        })
+        .collect()
+}
+
+/// Rows of walls with gaps at random positions.
+fn walls(random: &mut Random) -> Vec<NavigationObstacle> {
+    let mut obstacles = vec![];
+    for row in 1..10 {
+        let y = row as f64 * SIZE / 10.;
+        let mut x = 0.;
+        while x < SIZE {
+            let length = random.range(80., 300.);
+            let end = (x + length).min(SIZE);
+            let bend = Vec2::new((x + end) / 2., y + random.range(-30., 30.));
+            obstacles.push(NavigationObstacle::new_polyline(vec![
+                Vec2::new(x, y),
+                bend,
+                Vec2::new(end, y),
+            ]));
+            x = end + random.range(20., 60.);
+        }
+    }
+    obstacles
+}
+
+/// Many small squares on a grid, where the visibility graph is dense.
+fn pillars(random: &mut Random) -> Vec<NavigationObstacle> {
+    let mut obstacles = vec![];
+    for i in 0..12 {
+        for j in 0..12 {
+            let corner = Vec2::new(
+                i as f64 * 80. + random.range(20., 50.),
+                j as f64 * 80. + random.range(20., 50.),
+            );
+            obstacles.push(NavigationObstacle::new(vec![
+                corner,
+                corner + Vec2::new(15., 0.),
+                corner + Vec2::new(15., 15.),
+                corner + Vec2::new(0., 15.),
+            ]));
+        }
+    }
+    obstacles
+}
+
+fn properly_crosses(segment: Segment, other: Segment) -> bool {
+    let sides = [
+        orient2d(segment.p0, segment.p1, other.p0),
+        orient2d(segment.p0, segment.p1, other.p1),
+        orient2d(other.p0, other.p1, segment.p0),
+        orient2d(other.p0, other.p1, segment.p1),
+    ];
+    sides.iter().all(|&side| side != Sign::Zero) && sides[0] != sides[1] && sides[2] != sides[3]
+}
+
+fn is_visible(shapes: &[&Shape], a: Vec2, b: Vec2) -> bool {
+    let segment = Segment::new(a, b);
+    let midpoint = (a + b) / 2.;
+    shapes.iter().all(|shape| {
+        !shape.contains(midpoint)
+            && shape
+                .segments()
+                .into_iter()
+                .all(|edge| !properly_crosses(segment, edge))
+    })
+}
+
+/// The full visibility graph between all obstacle vertices. Like in `Navigation`, the first
+/// neighbor of every node is the end point, which is sliced off for nodes that can not see it.
+struct VisibilityGraph {
+    positions: Vec<Vec2>,
+    neighbors: Vec<Vec<usize>>,
+}
+
+impl VisibilityGraph {
+    fn new(shapes: &[&Shape]) -> Self {
+        let positions: Vec<Vec2> = shapes.iter().flat_map(|shape| shape.vertices.iter().copied()).collect();
+        let end = positions.len() + 1;
+        let mut neighbors = vec![vec![end]; positions.len()];
+        for a in 0..positions.len() {
+            for b in a + 1..positions.len() {
+                if is_visible(shapes, positions[a], positions[b]) {
+                    neighbors[a].push(b);
+                    neighbors[b].push(a);
+                }
+            }
+        }
+        VisibilityGraph { positions, neighbors }
+    }
+    fn edge_count(&self) -> usize {
+        self.neighbors
+            .iter()
+            .map(|neighbors| neighbors.len() - 1)
+            .sum::<usize>()
+            / 2
+    }
+    fn find_path(&self, shapes: &[&Shape], start: Vec2, end: Vec2) -> Option<Vec<usize>> {
+        let visible_from = |point: Vec2| -> Vec<bool> {
+            self.positions
+                .iter()
+                .map(|&position| is_visible(shapes, point, position))
+                .collect()
+        };
+        let start_visible = visible_from(start);
+        let mut start_neighbors: Vec<usize> = (0..self.positions.len()).filter(|&node| start_visible[node]).collect();
+        if is_visible(shapes, start, end) {
+            start_neighbors.push(self.positions.len() + 1);
+        }
+        a_star(&VisibilityGraphInput {
+            graph: self,
+            start,
+            end,
+            start_neighbors,
+            end_visible: visible_from(end),
+        })
+    }
+}
+
+struct VisibilityGraphInput<'a> {
+    graph: &'a VisibilityGraph,
+    start: Vec2,
+    end: Vec2,
+    start_neighbors: Vec<usize>,
+    end_visible: Vec<bool>,
+}
+
+impl VisibilityGraphInput<'_> {
+    fn position(&self, node: usize) -> Vec2 {
+        match node {
+            node if node == self.graph.positions.len() => self.start,
+            node if node == self.graph.positions.len() + 1 => self.end,
+            node => self.graph.positions[node],
+        }
+    }
+}
+
+impl AStarInput for VisibilityGraphInput<'_> {
+    fn neighbors(&self, node: usize) -> &[usize] {
+        if node == self.graph.positions.len() {
+            &self.start_neighbors
+        } else if self.end_visible[node] {
+            &self.graph.neighbors[node]
+        } else {
+            &self.graph.neighbors[node][1..]
+        }
+    }
+    fn distance(&self, from: usize, to: usize) -> N64 {
+        n64(self.position(from).dist(self.position(to)))
+    }
+    fn heuristic(&self, node: usize) -> N64 {
+        n64(self.position(node).dist(self.end))
+    }
+    fn len(&self) -> usize {
+        self.graph.positions.len() + 2
+    }
+    fn start(&self) -> usize {
+        self.graph.positions.len()
+    }
+    fn end(&self) -> usize {
+        self.graph.positions.len() + 1
+    }
+}
+
+/// Runs `query` on every pair of points, returning the average time in milliseconds.
+fn average_milliseconds<T>(queries: &[(Vec2, Vec2)], mut query: impl FnMut(Vec2, Vec2) -> T) -> f64 {
+    let start = Instant::now();
+    for &(from, to) in queries {
+        query(from, to);
+    }
+    start.elapsed().as_secs_f64() * 1000. / queries.len() as f64
+}
+
+/// Like `average_milliseconds`, but only times the search, after the end points have been
+/// connected to the navigation graph. Searches are short, so the fastest of a few rounds is taken.
+fn average_search_milliseconds(navigation: &Navigation, queries: &[(Vec2, Vec2)], options: &FindPathOptions) -> f64 {
+    (0..SEARCH_ROUNDS)
+        .map(|_| {
+            let mut searches: Vec<_> = queries
+                .iter()
+                .map(|&(from, to)| navigation.start_search_with_options(from, to, options))
+                .collect();
+            let start = Instant::now();
+            for search in &mut searches {
+                assert!(!matches!(
+                    search.step(AStarBudget::Unlimited),
+                    PathSearchStatus::Pending
+                ));
+            }
+            start.elapsed().as_secs_f64() * 1000. / queries.len() as f64
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn main() {
+    let mut random = Random(42);
+    let scenes: Vec<(&str, Vec<NavigationObstacle>)> = vec![
+        ("scattered polygons", scattered_polygons(&mut random)),
+        ("walls", walls(&mut random)),
+        ("pillars", pillars(&mut random)),
+    ];
+    println!(
+        "{:<20}{:>10}{:>12}{:>16}{:>12}{:>12}{:>12}{:>12}",
+        "", "", "visibility", "taut", "visibility", "navigation", "navigation", "taut"
+    );
+    println!(
+        "{:<20}{:>10}{:>12}{:>16}{:>12}{:>12}{:>12}{:>12}",
+        "scene", "vertices", "edges", "edges", "ms/query", "ms/query", "search ms", "search ms"
+    );
+    for (name, obstacles) in scenes {
+        let navigation = Navigation::new(obstacles);
+        let shapes = navigation.internal_obstacles();
+        let visibility_graph = VisibilityGraph::new(&shapes);
+        let queries: Vec<(Vec2, Vec2)> = (0..QUERIES)
+            .map(|_| {
+                (
+                    navigation.nearest_free_point(Vec2::new(random.range(0., SIZE), random.range(0., SIZE))),
+                    navigation.nearest_free_point(Vec2::new(random.range(0., SIZE), random.range(0., SIZE))),
+                )
+            })
+            .collect();
+        let taut_options = FindPathOptions {
+            taut_successors: true,
+            ..FindPathOptions::default()
+        };
+        for &(start, end) in &queries {
+            let length = navigation.find_path(start, end).map(|path| path_length(&path));
+            let taut_length = navigation
+                .find_path_with_options(start, end, &taut_options)
+                .ok()
+                .map(|path| path_length(&path.points));
+            assert_eq!(length.is_some(), taut_length.is_some());
+            if let (Some(length), Some(taut_length)) = (length, taut_length) {
+                assert!((length - taut_length).abs() < 1e-6);
+            }
+        }
+
+        let visibility_edges = visibility_graph.edge_count();
+        let taut_edges = navigation.internal_navigation_graph().len() / 2;
+        println!(
+            "{:<20}{:>10}{:>12}{:>9} ({:>3.0}%){:>12.3}{:>12.3}{:>12.4}{:>12.4}",
+            name,
+            visibility_graph.positions.len(),
+            visibility_edges,
+            taut_edges,
+            taut_edges as f64 / visibility_edges as f64 * 100.,
+            average_milliseconds(&queries, |start, end| visibility_graph.find_path(&shapes, start, end)),
+            average_milliseconds(&queries, |start, end| navigation.find_path(start, end)),
+            average_search_milliseconds(&navigation, &queries, &FindPathOptions::default()),
+            average_search_milliseconds(&navigation, &queries, &taut_options),
+        );
+    }
+}
@@ -1,9 +1,10 @@
 use noisy_float::prelude::*;
 
 use bv::BitVec;
-use std::cmp::Ordering;
+use std::cmp::{Ordering, Reverse};
 use std::collections::BinaryHeap;
 
+#[allow(clippy::len_without_is_empty)]
 pub trait AStarInput {
     fn neighbors(&self, node: usize) -> &[usize];
     fn distance(&self, from: usize, to: usize) -> N64;
@@ -11,6 +12,17 @@ pub trait AStarInput {
     fn len(&self) -> usize;
     fn start(&self) -> usize;
     fn end(&self) -> usize;
+    /// Whether `neighbor` is worth visiting from `node`, which has been reached from `parent`.
+    /// Inputs can use this to skip edges that are never part of a cheapest path when arriving
+    /// from `parent`. Not called for the start node, which has no parent.
+    fn is_successor(&self, _parent: usize, _node: usize, _neighbor: usize) -> bool {
+        true
+    }
+    /// The edges whose cost has changed, or which have been added or removed, since the input was
+    /// last passed to `DStarLite::update`. Ignored by all other searches.
+    fn changed_edges(&self) -> &[(usize, usize)] {
+        &[]
+    }
 }
 
 #[derive(Eq, PartialEq, Clone, Copy)]
@@ -34,47 +46,439 @@ impl PartialOrd for NodeCost {
     }
 }
 
+/// How much work a single call to `AStarSearch::step` is allowed to do.
+#[derive(Debug, Clone, Copy)]
+pub enum AStarBudget {
+    Unlimited,
+    /// Maximum number of nodes popped from the open queue.
+    Iterations(usize),
+    /// Stops after `milliseconds` have passed according to `now`. Since `std::time::Instant` is not
+    /// available on wasm, the clock has to be provided by the caller (e.g. `performance.now()`).
+    Time {
+        now: fn() -> f64,
+        milliseconds: f64,
+    },
+}
+
+/// Limits that make a search give up early instead of exploring the whole graph.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AStarLimits {
+    /// Nodes whose f-score exceeds this are never added to the open queue.
+    pub max_cost: Option<N64>,
+    /// Maximum number of nodes expanded over the whole search, not per step.
+    pub max_expansions: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AStarFailure {
+    /// Every reachable node has been expanded without reaching the end.
+    Unreachable,
+    /// The end could not be reached without exceeding `AStarLimits::max_cost`.
+    CostLimitReached,
+    ExpansionLimitReached,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AStarStatus {
+    /// The budget ran out before the search finished, call `step` again to continue.
+    Pending,
+    Found(Vec<usize>),
+    Failed(AStarFailure),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SearchState {
+    Searching,
+    Found,
+    Failed(AStarFailure),
+}
+
+/// An A* search whose state survives between calls, so that it can be spread across multiple
+/// frames. The same `AStarInput` has to be passed to every call to `step`.
+pub struct AStarSearch {
+    open_queue: BinaryHeap<NodeCost>,
+    closed_set: BitVec<usize>,
+    came_from: Vec<usize>,
+    g_score: Vec<N64>,
+    end: usize,
+    limits: AStarLimits,
+    expansions: usize,
+    /// Whether any node has been left out because of `AStarLimits::max_cost`
+    cost_pruned: bool,
+    state: SearchState,
+}
+
+impl AStarSearch {
+    pub fn new<Input>(input: &Input) -> Self
+    where
+        Input: AStarInput,
+    {
+        AStarSearch::with_limits(input, AStarLimits::default())
+    }
+    pub fn with_limits<Input>(input: &Input, limits: AStarLimits) -> Self
+    where
+        Input: AStarInput,
+    {
+        let start = input.start();
+        let mut g_score = vec![n64(f64::INFINITY); input.len()];
+        g_score[start] = n64(0.);
+        let mut search = AStarSearch {
+            open_queue: BinaryHeap::new(),
+            closed_set: BitVec::new_fill(false, input.len() as u64),
+            came_from: vec![usize::MAX; input.len()],
+            g_score,
+            end: input.end(),
+            limits,
+            expansions: 0,
+            cost_pruned: false,
+            state: SearchState::Searching,
+        };
+        search.push(start, input.heuristic(start));
+        search
+    }
+    fn push(&mut self, node: usize, f_score: N64) {
+        if let Some(max_cost) = self.limits.max_cost {
+            if f_score > max_cost {
+                self.cost_pruned = true;
+                return;
+            }
+        }
+        self.open_queue.push(NodeCost { node, f_score });
+    }
+    fn fail(&mut self, failure: AStarFailure) -> AStarStatus {
+        // Scores are kept so that the explored part of the graph can still be inspected
+        self.open_queue = BinaryHeap::new();
+        self.state = SearchState::Failed(failure);
+        AStarStatus::Failed(failure)
+    }
+    /// The cost of the cheapest path to `node` found so far, if any.
+    pub fn g_score(&self, node: usize) -> Option<N64> {
+        self.g_score
+            .get(node)
+            .copied()
+            .filter(|g_score| g_score.raw() != f64::INFINITY)
+    }
+    /// The lowest f-score in the open queue, or `None` if it is empty. Entries of nodes that have
+    /// been reached more cheaply since are only removed when popped, so this can be lower than the
+    /// f-score of the next node that is actually expanded.
+    pub fn min_open_f_score(&self) -> Option<N64> {
+        self.open_queue.peek().map(|node_cost| node_cost.f_score)
+    }
+    /// Reconstructs the cheapest path found so far from the start to `node`. Only meaningful if
+    /// `g_score(node)` is not `None`.
+    pub fn path_to(&self, node: usize) -> Vec<usize> {
+        let mut path = vec![];
+        let mut current = node;
+        while current != usize::MAX {
+            path.push(current);
+            current = self.came_from[current];
+        }
+        path.reverse();
+        path
+    }
+    pub fn step<Input>(&mut self, input: &Input, budget: AStarBudget) -> AStarStatus
+    where
+        Input: AStarInput,
+    {
+        match self.state {
+            SearchState::Found => return AStarStatus::Found(self.path_to(self.end)),
+            SearchState::Failed(failure) => return AStarStatus::Failed(failure),
+            SearchState::Searching => {}
+        }
+        let deadline = match budget {
+            AStarBudget::Time { now, milliseconds } => Some((now, now() + milliseconds)),
+            _ => None,
+        };
+        let mut iterations = 0;
+        loop {
+            if let AStarBudget::Iterations(max_iterations) = budget {
+                if iterations >= max_iterations {
+                    return AStarStatus::Pending;
+                }
+            }
+            if let Some((now, deadline)) = deadline {
+                // Always make some progress, no matter how small the budget is
+                if iterations > 0 && now() >= deadline {
+                    return AStarStatus::Pending;
+                }
+            }
+            iterations += 1;
+
+            let current = if let Some(NodeCost { node, .. }) = self.open_queue.pop() {
+                node
+            } else if self.cost_pruned {
+                return self.fail(AStarFailure::CostLimitReached);
+            } else {
+                return self.fail(AStarFailure::Unreachable);
+            };
+            if current == self.end {
+                self.state = SearchState::Found;
+                self.open_queue = BinaryHeap::new();
+                return AStarStatus::Found(self.path_to(current));
+            }
+            if self.closed_set[current as u64] {
+                continue;
+            }
+            if let Some(max_expansions) = self.limits.max_expansions {
+                if self.expansions >= max_expansions {
+                    return self.fail(AStarFailure::ExpansionLimitReached);
+                }
+            }
+            self.expansions += 1;
+            self.closed_set.set(current as u64, true);
+            let parent = self.came_from[current];
+            for &neighbor in input.neighbors(current) {
+                let tentative_g_score = self.g_score[current] + input.distance(current, neighbor);
+                if tentative_g_score < self.g_score[neighbor]
+                    && (parent == usize::MAX || input.is_successor(parent, current, neighbor))
+                {
+                    self.came_from[neighbor] = current;
+                    self.g_score[neighbor] = tentative_g_score;
+                    self.push(neighbor, tentative_g_score + input.heuristic(neighbor));
+                }
+            }
+        }
+    }
+    /// Gives up on the search and releases its memory. Any further call to `step` returns
+    /// `AStarFailure::Cancelled`.
+    pub fn cancel(&mut self) {
+        self.state = SearchState::Failed(AStarFailure::Cancelled);
+        self.open_queue = BinaryHeap::new();
+        self.closed_set = BitVec::new();
+        self.came_from = vec![];
+        self.g_score = vec![];
+    }
+}
+
+type DStarKey = (N64, N64);
+
+/// An incremental search (D* Lite) that repairs its results when edges change, instead of
+/// searching again from scratch. It searches backwards from `input.start()`, so that
+/// `input.end()` is free to move between updates, like an agent walking towards a fixed goal.
+///
+/// Neighbors have to be symmetric, and the heuristic consistent. Nodes keep their ids between
+/// updates, except for the start and the end, which may move (e.g. to stay after nodes that have
+/// been appended).
+pub struct DStarLite {
+    start: usize,
+    end: usize,
+    /// The cost of the cheapest path from every node to the start, as of the last expansion.
+    g_score: Vec<N64>,
+    /// The cost of the cheapest path from every node to the start, one step ahead of `g_score`.
+    rhs: Vec<N64>,
+    /// Added to all keys, since the heuristic shrinks as the end moves.
+    key_modifier: N64,
+    open_queue: BinaryHeap<Reverse<(DStarKey, usize)>>,
+    /// The key every node is queued with. Entries that do not match are stale and skipped.
+    queued_key: Vec<Option<DStarKey>>,
+}
+
+impl DStarLite {
+    pub fn new<Input>(input: &Input) -> Self
+    where
+        Input: AStarInput,
+    {
+        let mut search = DStarLite {
+            start: input.start(),
+            end: input.end(),
+            g_score: vec![n64(f64::INFINITY); input.len()],
+            rhs: vec![n64(f64::INFINITY); input.len()],
+            key_modifier: n64(0.),
+            open_queue: BinaryHeap::new(),
+            queued_key: vec![None; input.len()],
+        };
+        search.rhs[search.start] = n64(0.);
+        search.queue(input, search.start);
+        search.compute_shortest_path(input);
+        search
+    }
+    /// Brings the search up to date with `input.changed_edges()`. `heuristic_change` is at least
+    /// how much the heuristic of any node may have dropped since the last update, usually the
+    /// distance the end has moved.
+    pub fn update<Input>(&mut self, input: &Input, heuristic_change: N64)
+    where
+        Input: AStarInput,
+    {
+        self.key_modifier += heuristic_change;
+        if input.len() != self.g_score.len() || input.start() != self.start || input.end() != self.end {
+            self.renumber(input);
+        }
+        for &(node0, node1) in input.changed_edges() {
+            self.update_node(input, node0);
+            self.update_node(input, node1);
+        }
+        self.compute_shortest_path(input);
+    }
+    /// The cost of the cheapest path from `node` to the start, if any.
+    pub fn g_score(&self, node: usize) -> Option<N64> {
+        self.g_score
+            .get(node)
+            .copied()
+            .filter(|g_score| g_score.raw() != f64::INFINITY)
+    }
+    /// The cheapest path from the end to the start, or `None` if there is none.
+    pub fn path<Input>(&self, input: &Input) -> Option<Vec<usize>>
+    where
+        Input: AStarInput,
+    {
+        self.g_score(self.end)?;
+        let mut path = vec![self.end];
+        let mut current = self.end;
+        while current != self.start {
+            // Every step lowers the cost, so this can only loop if scores are inconsistent
+            if path.len() > input.len() {
+                return None;
+            }
+            current = input
+                .neighbors(current)
+                .iter()
+                .copied()
+                .filter(|&neighbor| self.g_score[neighbor].raw() != f64::INFINITY)
+                .min_by_key(|&neighbor| input.distance(current, neighbor) + self.g_score[neighbor])?;
+            path.push(current);
+        }
+        Some(path)
+    }
+    fn key<Input>(&self, input: &Input, node: usize) -> DStarKey
+    where
+        Input: AStarInput,
+    {
+        let cost = self.g_score[node].min(self.rhs[node]);
+        (cost + input.heuristic(node) + self.key_modifier, cost)
+    }
+    fn queue<Input>(&mut self, input: &Input, node: usize)
+    where
+        Input: AStarInput,
+    {
+        let key = self.key(input, node);
+        self.queued_key[node] = Some(key);
+        self.open_queue.push(Reverse((key, node)));
+    }
+    fn update_node<Input>(&mut self, input: &Input, node: usize)
+    where
+        Input: AStarInput,
+    {
+        if node != self.start {
+            self.rhs[node] = input
+                .neighbors(node)
+                .iter()
+                .map(|&neighbor| input.distance(node, neighbor) + self.g_score[neighbor])
+                .min()
+                .unwrap_or_else(|| n64(f64::INFINITY));
+        }
+        self.queued_key[node] = None;
+        if self.g_score[node] != self.rhs[node] {
+            self.queue(input, node);
+        }
+    }
+    /// The smallest key in the open queue, dropping stale entries on the way.
+    fn top_key(&mut self) -> Option<DStarKey> {
+        while let Some(&Reverse((key, node))) = self.open_queue.peek() {
+            if self.queued_key[node] == Some(key) {
+                return Some(key);
+            }
+            self.open_queue.pop();
+        }
+        None
+    }
+    fn compute_shortest_path<Input>(&mut self, input: &Input)
+    where
+        Input: AStarInput,
+    {
+        while let Some(top_key) = self.top_key() {
+            if top_key >= self.key(input, self.end) && self.g_score[self.end] == self.rhs[self.end] {
+                break;
+            }
+            let Reverse((old_key, node)) = self.open_queue.pop().unwrap();
+            self.queued_key[node] = None;
+            let new_key = self.key(input, node);
+            if old_key < new_key {
+                self.queue(input, node);
+            } else if self.g_score[node] > self.rhs[node] {
+                self.g_score[node] = self.rhs[node];
+                for &neighbor in input.neighbors(node) {
+                    self.update_node(input, neighbor);
+                }
+            } else {
+                self.g_score[node] = n64(f64::INFINITY);
+                self.update_node(input, node);
+                for &neighbor in input.neighbors(node) {
+                    self.update_node(input, neighbor);
+                }
+            }
+        }
+    }
+    /// Moves the scores of the start and the end to their new ids, and forgets any other node whose
+    /// id did not exist before.
+    fn renumber<Input>(&mut self, input: &Input)
+    where
+        Input: AStarInput,
+    {
+        let infinity = n64(f64::INFINITY);
+        let old_len = self.g_score.len();
+        let mut g_score = vec![infinity; input.len()];
+        let mut rhs = vec![infinity; input.len()];
+        for node in 0..old_len.min(input.len()) {
+            if node != self.start && node != self.end {
+                g_score[node] = self.g_score[node];
+                rhs[node] = self.rhs[node];
+            }
+        }
+        g_score[input.start()] = self.g_score[self.start];
+        rhs[input.start()] = self.rhs[self.start];
+        g_score[input.end()] = self.g_score[self.end];
+        rhs[input.end()] = self.rhs[self.end];
+        self.start = input.start();
+        self.end = input.end();
+        self.g_score = g_score;
+        self.rhs = rhs;
+        // Queued ids may have changed meaning, so queue everything inconsistent again
+        self.open_queue.clear();
+        self.queued_key = vec![None; input.len()];
+        for node in 0..input.len() {
+            if self.g_score[node] != self.rhs[node] {
+                self.queue(input, node);
+            }
+        }
+    }
+}
+
 pub fn a_star<Input>(input: &Input) -> Option<Vec<usize>>
 where
     Input: AStarInput,
 {
+    match AStarSearch::new(input).step(input, AStarBudget::Unlimited) {
+        AStarStatus::Found(path) => Some(path),
+        _ => None,
+    }
+}
+
+/// The cost of the cheapest path from `source` to every node, or `None` for nodes that cannot be
+/// reached. Only `neighbors`, `distance` and `len` of the input are used.
+pub fn dijkstra<Input>(input: &Input, source: usize) -> Vec<Option<N64>>
+where
+    Input: AStarInput,
+{
+    let mut distances = vec![None; input.len()];
     let mut open_queue = BinaryHeap::new();
-    let mut closed_set = BitVec::<usize>::new_fill(false, input.len() as u64);
-    let start = input.start();
-    let end = input.end();
     open_queue.push(NodeCost {
-        node: start,
-        f_score: input.heuristic(start),
+        node: source,
+        f_score: n64(0.),
     });
-    let mut came_from = vec![usize::MAX; input.len()];
-    let mut g_score = vec![n64(f64::INFINITY); input.len()];
-    g_score[start] = n64(0.);
-    while let Some(NodeCost { node: current, .. }) = open_queue.pop() {
-        if current == end {
-            let mut path = vec![];
-            let mut current = current;
-            while current != usize::MAX {
-                path.push(current);
-                current = came_from[current];
-            }
-            path.reverse();
-            return Some(path);
-        }
-        if closed_set[current as u64] {
+    while let Some(NodeCost { node, f_score }) = open_queue.pop() {
+        if distances[node].is_some() {
             continue;
         }
-        for &neighbor in input.neighbors(current) {
-            let tentative_g_score = g_score[current] + input.distance(current, neighbor);
-            if tentative_g_score < g_score[neighbor] {
-                came_from[neighbor] = current;
-                g_score[neighbor] = tentative_g_score;
+        distances[node] = Some(f_score);
+        for &neighbor in input.neighbors(node) {
+            if distances[neighbor].is_none() {
                 open_queue.push(NodeCost {
                     node: neighbor,
-                    f_score: tentative_g_score + input.heuristic(neighbor),
+                    f_score: f_score + input.distance(node, neighbor),
                 });
-                closed_set.set(current as u64, true);
             }
         }
     }
-    None
+    distances
 }
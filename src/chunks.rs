@@ -0,0 +1,334 @@
+//! Navigation for worlds that are streamed in square chunks. Every loaded chunk has its own
+//! `Navigation` over its obstacles clipped at the chunk borders, and loaded neighbors are stitched
+//! together by entrances on the free parts of their shared border, see `hierarchical`. Chunks can
+//! be loaded and unloaded at any time, only the paths within the affected chunks are recomputed.
+
+use std::collections::HashMap;
+
+use crate::geometry::{
+    boolean_operation, orient2d, path_length, point_key, Aabb, BooleanOperation, Polygon, Segment, Shape, Sign, Vec2,
+};
+use crate::hierarchical::{border_entrances, AbstractGraph, AbstractRegions};
+use crate::navigation::{Navigation, NavigationObstacle, PathFinder};
+
+pub type ChunkCoords = (i32, i32);
+
+struct Chunk {
+    bounds: Aabb,
+    obstacles: Vec<Polygon>,
+    navigation: Navigation,
+    /// Obstacle edges that lie on the chunk border. Clipping can split an obstacle there, so a
+    /// path along such an edge may run through the obstacle.
+    border_edges: Vec<Segment>,
+    /// The lengths of the paths between the entrances of the chunk.
+    links: Vec<(Vec2, Vec2, f64)>,
+    entrances: Vec<usize>,
+}
+
+impl Chunk {
+    fn find_path(&self, start: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
+        let path = self.navigation.find_path(start, end)?;
+        let along_border_edge = |a: Vec2, b: Vec2| {
+            self.border_edges.iter().any(|edge| {
+                let direction = edge.vec().normalize();
+                orient2d(edge.p0, edge.p1, a) == Sign::Zero
+                    && orient2d(edge.p0, edge.p1, b) == Sign::Zero
+                    && overlap(
+                        (a - edge.p0).dot(direction),
+                        (b - edge.p0).dot(direction),
+                        0.,
+                        edge.vec().magnitude(),
+                    ) > 1e-9
+            })
+        };
+        if path.iter().all(|&point| self.bounds.contains(point))
+            && !path.windows(2).any(|points| along_border_edge(points[0], points[1]))
+        {
+            Some(path)
+        } else {
+            None
+        }
+    }
+}
+
+/// The length of the overlap of two intervals given by their ends in any order.
+fn overlap(a0: f64, a1: f64, b0: f64, b1: f64) -> f64 {
+    (a0.max(a1).min(b0.max(b1)) - a0.min(a1).max(b0.min(b1))).max(0.)
+}
+
+pub struct ChunkedNavigation {
+    chunk_size: f64,
+    chunks: HashMap<ChunkCoords, Chunk>,
+    /// The entrances on the border between two loaded chunks, keyed by the coordinates of both
+    /// chunks in ascending order.
+    borders: HashMap<(ChunkCoords, ChunkCoords), Vec<Vec2>>,
+    graph: AbstractGraph<ChunkCoords>,
+}
+
+impl ChunkedNavigation {
+    /// Chunk `(x, y)` covers `x * chunk_size` to `(x + 1) * chunk_size` horizontally, and likewise
+    /// vertically.
+    pub fn new(chunk_size: f64) -> Self {
+        ChunkedNavigation {
+            chunk_size,
+            chunks: HashMap::new(),
+            borders: HashMap::new(),
+            graph: AbstractGraph::new(),
+        }
+    }
+    pub fn chunk_at(&self, point: Vec2) -> ChunkCoords {
+        (
+            (point.x / self.chunk_size).floor() as i32,
+            (point.y / self.chunk_size).floor() as i32,
+        )
+    }
+    pub fn chunk_bounds(&self, (x, y): ChunkCoords) -> Aabb {
+        let min = Vec2::new(x as f64, y as f64) * self.chunk_size;
+        Aabb::new(min, min + Vec2::new(self.chunk_size, self.chunk_size))
+    }
+    pub fn is_loaded(&self, coords: ChunkCoords) -> bool {
+        self.chunks.contains_key(&coords)
+    }
+    pub fn loaded_chunks(&self) -> impl Iterator<Item = ChunkCoords> + '_ {
+        self.chunks.keys().copied()
+    }
+    fn neighbors((x, y): ChunkCoords) -> [ChunkCoords; 4] {
+        [(x + 1, y), (x, y + 1), (x - 1, y), (x, y - 1)]
+    }
+    fn border_key(a: ChunkCoords, b: ChunkCoords) -> (ChunkCoords, ChunkCoords) {
+        (a.min(b), a.max(b))
+    }
+    /// Loads the chunk at `coords`, replacing it if it is already loaded. The obstacles are clipped
+    /// to the chunk, so they may extend beyond it.
+    pub fn load_chunk(&mut self, coords: ChunkCoords, obstacles: &[Polygon]) {
+        let bounds = self.chunk_bounds(coords);
+        let obstacles = boolean_operation(
+            obstacles,
+            &[Polygon::from(Shape::new(bounds.vertices().to_vec()))],
+            BooleanOperation::Intersection,
+        );
+        let navigation = Navigation::new(obstacles.iter().map(NavigationObstacle::from_polygon).collect());
+        let border_edges = obstacles
+            .iter()
+            .flat_map(Polygon::rings)
+            .flat_map(Shape::segments)
+            .filter(|edge| {
+                bounds.segments().iter().any(|side| {
+                    orient2d(side.p0, side.p1, edge.p0) == Sign::Zero
+                        && orient2d(side.p0, side.p1, edge.p1) == Sign::Zero
+                })
+            })
+            .collect();
+        self.chunks.insert(
+            coords,
+            Chunk {
+                bounds,
+                obstacles,
+                navigation,
+                border_edges,
+                links: vec![],
+                entrances: vec![],
+            },
+        );
+        let (x, y) = coords;
+        for neighbor in Self::neighbors(coords) {
+            let (chunk, neighbor_chunk) = match (self.chunks.get(&coords), self.chunks.get(&neighbor)) {
+                (Some(chunk), Some(neighbor_chunk)) => (chunk, neighbor_chunk),
+                _ => continue,
+            };
+            let [v0, v1, v2, v3] = bounds.vertices();
+            let border = match (neighbor.0 - x, neighbor.1 - y) {
+                (1, _) => Segment::new(v1, v2),
+                (_, 1) => Segment::new(v3, v2),
+                (-1, _) => Segment::new(v0, v3),
+                _ => Segment::new(v0, v1),
+            };
+            let shapes: Vec<&Shape> = chunk
+                .obstacles
+                .iter()
+                .chain(&neighbor_chunk.obstacles)
+                .flat_map(Polygon::rings)
+                .collect();
+            let entrances = border_entrances(border, &shapes, self.chunk_size / 4.);
+            self.borders.insert(Self::border_key(coords, neighbor), entrances);
+        }
+        self.update_links(coords);
+        for neighbor in Self::neighbors(coords) {
+            self.update_links(neighbor);
+        }
+        self.rebuild_graph();
+    }
+    /// Unloads the chunk at `coords`. Returns whether it was loaded.
+    pub fn unload_chunk(&mut self, coords: ChunkCoords) -> bool {
+        if self.chunks.remove(&coords).is_none() {
+            return false;
+        }
+        for neighbor in Self::neighbors(coords) {
+            self.borders.remove(&Self::border_key(coords, neighbor));
+            self.update_links(neighbor);
+        }
+        self.rebuild_graph();
+        true
+    }
+    /// Recomputes the paths between the entrances of a chunk, if it is loaded.
+    fn update_links(&mut self, coords: ChunkCoords) {
+        let entrances: Vec<Vec2> = Self::neighbors(coords)
+            .iter()
+            .filter_map(|&neighbor| self.borders.get(&Self::border_key(coords, neighbor)))
+            .flatten()
+            .copied()
+            .collect();
+        let chunk = match self.chunks.get_mut(&coords) {
+            Some(chunk) => chunk,
+            None => return,
+        };
+        chunk.links.clear();
+        for (index, &entrance0) in entrances.iter().enumerate() {
+            for &entrance1 in &entrances[index + 1..] {
+                if let Some(path) = chunk.find_path(entrance0, entrance1) {
+                    chunk.links.push((entrance0, entrance1, path_length(&path)));
+                }
+            }
+        }
+    }
+    fn rebuild_graph(&mut self) {
+        let mut graph = AbstractGraph::new();
+        let mut entrance_ids = HashMap::new();
+        for chunk in self.chunks.values_mut() {
+            chunk.entrances.clear();
+        }
+        for (&(a, b), entrances) in &self.borders {
+            for &position in entrances {
+                let entrance = graph.add_entrance(position);
+                entrance_ids.insert(point_key(position), entrance);
+                for coords in [a, b] {
+                    self.chunks.get_mut(&coords).unwrap().entrances.push(entrance);
+                }
+            }
+        }
+        for (&coords, chunk) in &self.chunks {
+            for &(entrance0, entrance1, cost) in &chunk.links {
+                graph.connect(
+                    entrance_ids[&point_key(entrance0)],
+                    entrance_ids[&point_key(entrance1)],
+                    cost,
+                    coords,
+                );
+            }
+        }
+        self.graph = graph;
+    }
+    /// Finds a path within the loaded chunks, or `None` if the start or end point is not loaded or
+    /// every path would have to leave the loaded area.
+    pub fn find_path(&self, start: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
+        let start_chunk = self.chunk_at(start);
+        let end_chunk = self.chunk_at(end);
+        if !self.is_loaded(start_chunk) || !self.is_loaded(end_chunk) {
+            return None;
+        }
+        self.graph
+            .find_path(&self.chunks, (start, start_chunk), (end, end_chunk))
+    }
+}
+
+impl AbstractRegions for HashMap<ChunkCoords, Chunk> {
+    type Region = ChunkCoords;
+    fn entrances(&self, region: ChunkCoords) -> &[usize] {
+        &self[&region].entrances
+    }
+    fn local_path(&self, region: ChunkCoords, start: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
+        self[&region].find_path(start, end)
+    }
+}
+
+impl PathFinder for ChunkedNavigation {
+    fn find_path(&self, start: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
+        self.find_path(start, end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{path_crosses, square};
+
+    fn world() -> Vec<Polygon> {
+        // The first square spans four chunks
+        let squares = [square(40., 40., 20.), square(70., 10., 15.), square(120., 30., 25.)];
+        let walls = vec![
+            // A wall along the border between the two rows, with a gap at x = 115
+            Polygon::from(Shape::new(vec![
+                Vec2::new(-100., 48.),
+                Vec2::new(110., 48.),
+                Vec2::new(110., 52.),
+                Vec2::new(-100., 52.),
+            ])),
+            Polygon::from(Shape::new(vec![
+                Vec2::new(120., 48.),
+                Vec2::new(250., 48.),
+                Vec2::new(250., 52.),
+                Vec2::new(120., 52.),
+            ])),
+        ];
+        squares
+            .iter()
+            .map(|square| Polygon::from(square.shape().clone()))
+            .chain(walls)
+            .collect()
+    }
+
+    fn load_all(chunked: &mut ChunkedNavigation) {
+        for x in 0..3 {
+            for y in 0..2 {
+                chunked.load_chunk((x, y), &world());
+            }
+        }
+    }
+
+    #[test]
+    fn stitched_paths_close_to_navigation() {
+        let mut chunked = ChunkedNavigation::new(50.);
+        load_all(&mut chunked);
+        let world = boolean_operation(&world(), &[], BooleanOperation::Union);
+        let navigation = Navigation::new(world.iter().map(NavigationObstacle::from_polygon).collect());
+        let points = [
+            Vec2::new(2., 2.),
+            Vec2::new(148., 98.),
+            Vec2::new(20., 90.),
+            Vec2::new(110., 5.),
+            Vec2::new(65., 65.),
+        ];
+        for &start in &points {
+            for &end in &points {
+                let optimal = path_length(&navigation.find_path(start, end).unwrap());
+                let path = chunked.find_path(start, end).unwrap();
+                assert_eq!((path[0], path[path.len() - 1]), (start, end));
+                assert!(!path_crosses(&path, world.iter().flat_map(Polygon::rings)));
+                let length = path_length(&path);
+                assert!(length >= optimal - 1e-9);
+                assert!(length <= optimal * 1.15, "{} > {} * 1.15", length, optimal);
+            }
+        }
+    }
+
+    #[test]
+    fn load_and_unload() {
+        let mut chunked = ChunkedNavigation::new(50.);
+        load_all(&mut chunked);
+        let (start, end) = (Vec2::new(20., 30.), Vec2::new(20., 70.));
+        assert!(chunked.find_path(start, end).unwrap().len() > 2);
+        // The only way through the wall is in chunk (2, 0) or (2, 1)
+        assert!(chunked.unload_chunk((2, 1)));
+        assert!(!chunked.unload_chunk((2, 1)));
+        assert_eq!(chunked.find_path(start, end), None);
+        assert_eq!(chunked.find_path(start, Vec2::new(120., 70.)), None);
+        chunked.load_chunk((2, 1), &world());
+        assert!(chunked.find_path(start, end).is_some());
+        // Reloading with a changed world
+        chunked.load_chunk((2, 0), &[]);
+        chunked.load_chunk((2, 1), &[]);
+        let (start, end) = (Vec2::new(110., 5.), Vec2::new(140., 95.));
+        let path = chunked.find_path(start, end).unwrap();
+        assert!(path_length(&path) < start.dist(end) + 1.);
+    }
+}
@@ -0,0 +1,351 @@
+//! Contraction hierarchies over the navigation graph of `Navigation`, for answering many queries
+//! on a static map. Nodes are contracted one after another, adding shortcuts between their
+//! neighbors wherever the node was on the only shortest path between them. A query then only
+//! searches upwards in the contraction order from both end points, until the searches meet.
+//!
+//! The found paths have the same length as the ones of `Navigation::find_path`.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+
+use noisy_float::prelude::*;
+use noisy_float::types::N64;
+
+use crate::geometry::Vec2;
+use crate::navigation::{Navigation, PathFinder};
+
+/// How many nodes a witness search may settle. A search that gives up too early only adds a
+/// shortcut that is not needed.
+const WITNESS_SETTLE_LIMIT: usize = 64;
+
+#[derive(Eq, PartialEq, Clone, Copy)]
+struct NodeCost {
+    node: usize,
+    cost: N64,
+}
+
+impl Ord for NodeCost {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.cmp(&self.cost).then_with(|| self.node.cmp(&other.node))
+    }
+}
+
+impl PartialOrd for NodeCost {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The graph during contraction, with the cost of every edge and, for shortcuts, the contracted
+/// node it bypasses.
+struct ContractionGraph {
+    edges: Vec<HashMap<usize, (f64, Option<usize>)>>,
+    contracted: Vec<bool>,
+}
+
+impl ContractionGraph {
+    fn remaining_neighbors(&self, node: usize) -> Vec<(usize, f64)> {
+        let mut neighbors: Vec<(usize, f64)> = self.edges[node]
+            .iter()
+            .filter(|(&neighbor, _)| !self.contracted[neighbor])
+            .map(|(&neighbor, &(cost, _))| (neighbor, cost))
+            .collect();
+        // Keeps the contraction order independent of the iteration order of the map
+        neighbors.sort_by_key(|&(neighbor, _)| neighbor);
+        neighbors
+    }
+    /// The shortcuts needed when `node` is contracted, as pairs of its neighbors with the cost of
+    /// the path through it.
+    fn shortcuts(&self, node: usize) -> Vec<(usize, usize, f64)> {
+        let neighbors = self.remaining_neighbors(node);
+        let max_outgoing = neighbors.iter().map(|&(_, cost)| cost).fold(0., f64::max);
+        let mut shortcuts = vec![];
+        for (index, &(source, source_cost)) in neighbors.iter().enumerate() {
+            let witness_costs = self.witness_search(source, node, source_cost + max_outgoing);
+            for &(target, target_cost) in &neighbors[index + 1..] {
+                let cost = source_cost + target_cost;
+                if !matches!(witness_costs.get(&target), Some(&witness_cost) if witness_cost <= cost) {
+                    shortcuts.push((source, target, cost));
+                }
+            }
+        }
+        shortcuts
+    }
+    /// Costs of the cheapest paths from `source` that avoid `avoided` and cost at most `max_cost`.
+    /// Nodes that are too far or not reached before the settle limit are left out.
+    fn witness_search(&self, source: usize, avoided: usize, max_cost: f64) -> HashMap<usize, f64> {
+        let mut costs = HashMap::new();
+        let mut open_queue = BinaryHeap::new();
+        open_queue.push(NodeCost {
+            node: source,
+            cost: n64(0.),
+        });
+        let mut settled = 0;
+        while let Some(NodeCost { node, cost }) = open_queue.pop() {
+            if costs.contains_key(&node) {
+                continue;
+            }
+            costs.insert(node, cost.raw());
+            settled += 1;
+            if settled >= WITNESS_SETTLE_LIMIT {
+                break;
+            }
+            for (&neighbor, &(edge_cost, _)) in &self.edges[node] {
+                let neighbor_cost = cost.raw() + edge_cost;
+                if neighbor != avoided
+                    && !self.contracted[neighbor]
+                    && !costs.contains_key(&neighbor)
+                    && neighbor_cost <= max_cost
+                {
+                    open_queue.push(NodeCost {
+                        node: neighbor,
+                        cost: n64(neighbor_cost),
+                    });
+                }
+            }
+        }
+        costs
+    }
+    /// How attractive it is to contract `node` next, lower is better. Prefers nodes that add few
+    /// shortcuts compared to the edges they remove, and spreads the contraction over the graph.
+    fn priority(&self, node: usize, shortcut_count: usize, contracted_neighbors: usize) -> i64 {
+        shortcut_count as i64 - self.remaining_neighbors(node).len() as i64 + contracted_neighbors as i64
+    }
+}
+
+pub struct ContractionHierarchy {
+    navigation: Navigation,
+    /// For every node, the edges to nodes that have been contracted after it, including shortcuts.
+    upward: Vec<Vec<(usize, f64)>>,
+    /// The node a shortcut bypasses, keyed by both of its ends in ascending order.
+    shortcuts: HashMap<(usize, usize), usize>,
+}
+
+fn edge_key(node0: usize, node1: usize) -> (usize, usize) {
+    (node0.min(node1), node0.max(node1))
+}
+
+impl ContractionHierarchy {
+    pub fn new(navigation: Navigation) -> Self {
+        let len = navigation.graph_len();
+        let mut graph = ContractionGraph {
+            edges: (0..len)
+                .map(|node| {
+                    let position = navigation.graph_position(node);
+                    navigation
+                        .graph_neighbors(node)
+                        .iter()
+                        .map(|&neighbor| (neighbor, (position.dist(navigation.graph_position(neighbor)), None)))
+                        .collect()
+                })
+                .collect(),
+            contracted: vec![false; len],
+        };
+        let mut contracted_neighbors = vec![0; len];
+        let mut queue: BinaryHeap<Reverse<(i64, usize)>> = (0..len)
+            .map(|node| Reverse((graph.priority(node, graph.shortcuts(node).len(), 0), node)))
+            .collect();
+        let mut rank = vec![0; len];
+        let mut next_rank = 0;
+        while let Some(Reverse((_, node))) = queue.pop() {
+            if graph.contracted[node] {
+                continue;
+            }
+            // The priority may be outdated since contracting the neighbors
+            let shortcuts = graph.shortcuts(node);
+            let priority = graph.priority(node, shortcuts.len(), contracted_neighbors[node]);
+            if matches!(queue.peek(), Some(&Reverse((next_priority, _))) if priority > next_priority) {
+                queue.push(Reverse((priority, node)));
+                continue;
+            }
+            graph.contracted[node] = true;
+            rank[node] = next_rank;
+            next_rank += 1;
+            for (neighbor, _) in graph.remaining_neighbors(node) {
+                contracted_neighbors[neighbor] += 1;
+            }
+            for (source, target, cost) in shortcuts {
+                if !matches!(graph.edges[source].get(&target), Some(&(edge_cost, _)) if edge_cost <= cost) {
+                    graph.edges[source].insert(target, (cost, Some(node)));
+                    graph.edges[target].insert(source, (cost, Some(node)));
+                }
+            }
+        }
+
+        let mut upward = vec![vec![]; len];
+        let mut shortcuts = HashMap::new();
+        for (node, edges) in graph.edges.iter().enumerate() {
+            for (&neighbor, &(cost, middle)) in edges {
+                if rank[neighbor] > rank[node] {
+                    upward[node].push((neighbor, cost));
+                    if let Some(middle) = middle {
+                        shortcuts.insert(edge_key(node, neighbor), middle);
+                    }
+                }
+            }
+        }
+        ContractionHierarchy {
+            navigation,
+            upward,
+            shortcuts,
+        }
+    }
+    pub fn navigation(&self) -> &Navigation {
+        &self.navigation
+    }
+    pub fn shortcut_count(&self) -> usize {
+        self.shortcuts.len()
+    }
+    /// Searches upwards from every node connected to `point`. Returns the cost of the cheapest path
+    /// to every reached node, and the node it is reached from, which is `None` for nodes connected
+    /// to `point` directly.
+    fn upward_search(&self, point: Vec2) -> HashMap<usize, (f64, Option<usize>)> {
+        let mut reached: HashMap<usize, (f64, Option<usize>)> = HashMap::new();
+        let mut open_queue = BinaryHeap::new();
+        for node in self.navigation.connectable_nodes(point) {
+            let cost = point.dist(self.navigation.graph_position(node));
+            reached.insert(node, (cost, None));
+            open_queue.push(NodeCost { node, cost: n64(cost) });
+        }
+        while let Some(NodeCost { node, cost }) = open_queue.pop() {
+            if cost.raw() > reached[&node].0 {
+                continue;
+            }
+            for &(neighbor, edge_cost) in &self.upward[node] {
+                let neighbor_cost = cost.raw() + edge_cost;
+                if !matches!(reached.get(&neighbor), Some(&(reached_cost, _)) if reached_cost <= neighbor_cost) {
+                    reached.insert(neighbor, (neighbor_cost, Some(node)));
+                    open_queue.push(NodeCost {
+                        node: neighbor,
+                        cost: n64(neighbor_cost),
+                    });
+                }
+            }
+        }
+        reached
+    }
+    /// Appends the nodes of the original graph between `from` and `to` to `path`, excluding
+    /// `from`.
+    fn unpack(&self, from: usize, to: usize, path: &mut Vec<usize>) {
+        match self.shortcuts.get(&edge_key(from, to)) {
+            Some(&middle) => {
+                self.unpack(from, middle, path);
+                self.unpack(middle, to, path);
+            }
+            None => path.push(to),
+        }
+    }
+    pub fn find_path(&self, start: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
+        if self.navigation.has_line_of_sight(start, end) {
+            return Some(vec![start, end]);
+        }
+        let forward = self.upward_search(start);
+        let backward = self.upward_search(end);
+        let (meeting_node, _) = forward
+            .iter()
+            .filter_map(|(&node, &(forward_cost, _))| {
+                backward
+                    .get(&node)
+                    .map(|&(backward_cost, _)| (node, forward_cost + backward_cost))
+            })
+            .min_by(|(node0, cost0), (node1, cost1)| cost0.partial_cmp(cost1).unwrap().then(node0.cmp(node1)))?;
+
+        // The nodes from the start to the meeting node, and from there to the end
+        let mut up_nodes = vec![meeting_node];
+        while let Some(node) = forward[up_nodes.last().unwrap()].1 {
+            up_nodes.push(node);
+        }
+        up_nodes.reverse();
+        let mut down_nodes = vec![];
+        let mut current = meeting_node;
+        while let Some(node) = backward[&current].1 {
+            down_nodes.push(node);
+            current = node;
+        }
+        let mut nodes = vec![up_nodes[0]];
+        for pair in up_nodes.windows(2) {
+            self.unpack(pair[0], pair[1], &mut nodes);
+        }
+        let mut previous = meeting_node;
+        for node in down_nodes {
+            self.unpack(previous, node, &mut nodes);
+            previous = node;
+        }
+
+        let mut path = vec![start];
+        path.extend(nodes.into_iter().map(|node| self.navigation.graph_position(node)));
+        path.push(end);
+        Some(path)
+    }
+}
+
+impl PathFinder for ContractionHierarchy {
+    fn find_path(&self, start: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
+        self.find_path(start, end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{path_length, Polygon};
+    use crate::navigation::NavigationObstacle;
+    use crate::test_utils::{square, square_grid};
+
+    fn obstacles() -> Vec<NavigationObstacle> {
+        let mut obstacles = square_grid(Vec2::zero(), 6, 20., Vec2::new(1.5, 0.7), |i, j| {
+            if (i * 7 + j * 3) % 5 == 0 {
+                13.
+            } else {
+                7.
+            }
+        });
+        obstacles.push(NavigationObstacle::new_polyline(vec![
+            Vec2::new(-10., 55.),
+            Vec2::new(60., 58.),
+            Vec2::new(65., 100.),
+        ]));
+        obstacles
+    }
+
+    #[test]
+    fn same_paths_as_navigation() {
+        let navigation = Navigation::new(obstacles());
+        let hierarchy = ContractionHierarchy::new(Navigation::new(obstacles()));
+        assert!(hierarchy.shortcut_count() > 0);
+        let points = [
+            Vec2::new(-10., -10.),
+            Vec2::new(130., 125.),
+            Vec2::new(50., -5.),
+            Vec2::new(-5., 70.),
+            Vec2::new(69., 50.),
+            Vec2::new(90., 10.),
+            Vec2::new(17., 37.),
+        ];
+        for &start in &points {
+            for &end in &points {
+                let expected = navigation.find_path(start, end).unwrap();
+                let path = hierarchy.find_path(start, end).unwrap();
+                assert!((path_length(&path) - path_length(&expected)).abs() < 1e-9);
+                assert_eq!(path, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn unreachable() {
+        // A closed room with a pillar inside
+        let room = Polygon::new(
+            square(0., 0., 50.).shape().clone(),
+            vec![square(5., 5., 40.).shape().clone()],
+        );
+        let obstacles = vec![NavigationObstacle::from_polygon(&room), square(20., 20., 10.)];
+        let hierarchy = ContractionHierarchy::new(Navigation::new(obstacles.clone()));
+        let inside = Vec2::new(10., 10.);
+        assert_eq!(hierarchy.find_path(inside, Vec2::new(70., 70.)), None);
+        assert_eq!(hierarchy.find_path(Vec2::new(-10., 70.), inside), None);
+        let path = hierarchy.find_path(inside, Vec2::new(40., 35.));
+        assert!(path.is_some());
+        assert_eq!(path, Navigation::new(obstacles).find_path(inside, Vec2::new(40., 35.)));
+    }
+}
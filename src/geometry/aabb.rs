@@ -0,0 +1,45 @@
+use super::{Segment, Vec2};
+
+/// Axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Aabb {
+    pub fn new(min: Vec2, max: Vec2) -> Self {
+        debug_assert!(min.x <= max.x && min.y <= max.y);
+        Aabb { min, max }
+    }
+    pub fn width(&self) -> f64 {
+        self.max.x - self.min.x
+    }
+    pub fn height(&self) -> f64 {
+        self.max.y - self.min.y
+    }
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.min.x <= point.x && point.x <= self.max.x && self.min.y <= point.y && point.y <= self.max.y
+    }
+    /// Grows the box by `delta` in every direction.
+    pub fn expand(&self, delta: f64) -> Self {
+        Aabb::new(self.min - Vec2::new(delta, delta), self.max + Vec2::new(delta, delta))
+    }
+    pub fn vertices(&self) -> [Vec2; 4] {
+        [
+            self.min,
+            Vec2::new(self.max.x, self.min.y),
+            self.max,
+            Vec2::new(self.min.x, self.max.y),
+        ]
+    }
+    pub fn segments(&self) -> [Segment; 4] {
+        let [v0, v1, v2, v3] = self.vertices();
+        [
+            Segment::new(v0, v1),
+            Segment::new(v1, v2),
+            Segment::new(v2, v3),
+            Segment::new(v3, v0),
+        ]
+    }
+}
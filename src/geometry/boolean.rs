@@ -0,0 +1,347 @@
+use std::collections::{HashMap, HashSet};
+use std::f64::consts::PI;
+
+use super::{orient2d, point_key, PointKey, Polygon, Segment, Shape, Sign, Vec2};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BooleanOperation {
+    Union,
+    Intersection,
+    Difference,
+    Xor,
+}
+
+impl BooleanOperation {
+    fn apply(self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            BooleanOperation::Union => in_a || in_b,
+            BooleanOperation::Intersection => in_a && in_b,
+            BooleanOperation::Difference => in_a && !in_b,
+            BooleanOperation::Xor => in_a != in_b,
+        }
+    }
+}
+
+/// The normal of `direction` pointing to its left in a y-up coordinate system.
+fn left_normal(direction: Vec2) -> Vec2 {
+    Vec2::new(-direction.y, direction.x)
+}
+
+fn is_collinear(segment0: &Segment, segment1: &Segment) -> bool {
+    orient2d(segment0.p0, segment0.p1, segment1.p0) == Sign::Zero
+        && orient2d(segment0.p0, segment0.p1, segment1.p1) == Sign::Zero
+}
+
+/// Whether `point`, which is collinear with `segment`, lies on it.
+fn is_on_collinear_segment(segment: &Segment, point: Vec2) -> bool {
+    segment.p0.x.min(segment.p1.x) <= point.x
+        && point.x <= segment.p0.x.max(segment.p1.x)
+        && segment.p0.y.min(segment.p1.y) <= point.y
+        && point.y <= segment.p0.y.max(segment.p1.y)
+}
+
+/// Splits every edge at the points where it touches any other edge, and removes duplicates, so
+/// that edges only meet at their endpoints.
+fn split_edges(edges: &[Segment]) -> Vec<Segment> {
+    let mut split_points: Vec<Vec<Vec2>> = edges.iter().map(|edge| vec![edge.p0, edge.p1]).collect();
+    for (edge0_index, edge0) in edges.iter().enumerate() {
+        for (edge1_index, edge1) in edges.iter().enumerate().skip(edge0_index + 1) {
+            if is_collinear(edge0, edge1) {
+                for &point in &[edge1.p0, edge1.p1] {
+                    if is_on_collinear_segment(edge0, point) {
+                        split_points[edge0_index].push(point);
+                    }
+                }
+                for &point in &[edge0.p0, edge0.p1] {
+                    if is_on_collinear_segment(edge1, point) {
+                        split_points[edge1_index].push(point);
+                    }
+                }
+            } else if let Some(point) = edge0.intersect(edge1) {
+                // Both edges are split at the very same point, even if it has been rounded
+                split_points[edge0_index].push(point);
+                split_points[edge1_index].push(point);
+            }
+        }
+    }
+    let mut seen: HashSet<(PointKey, PointKey)> = HashSet::new();
+    let mut split_edges = vec![];
+    for (edge, mut points) in edges.iter().zip(split_points) {
+        let direction = edge.vec();
+        points.sort_by(|point0, point1| {
+            let t0 = (*point0 - edge.p0).dot(direction);
+            let t1 = (*point1 - edge.p0).dot(direction);
+            t0.partial_cmp(&t1).unwrap()
+        });
+        points.dedup();
+        for pair in points.windows(2) {
+            let (key0, key1) = (point_key(pair[0]), point_key(pair[1]));
+            if key0 != key1 && seen.insert((key0.min(key1), key0.max(key1))) {
+                split_edges.push(Segment::new(pair[0], pair[1]));
+            }
+        }
+    }
+    split_edges
+}
+
+/// Chains edges into closed rings. Every edge has the inside on its left, and at vertices where
+/// multiple rings meet, the sharpest left turn is taken, so that touching rings are kept apart.
+fn chain_edges(edges: &[Segment]) -> Vec<Vec<Vec2>> {
+    let mut outgoing: HashMap<PointKey, Vec<usize>> = HashMap::new();
+    for (edge_index, edge) in edges.iter().enumerate() {
+        outgoing.entry(point_key(edge.p0)).or_default().push(edge_index);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut rings = vec![];
+    for first_edge_index in 0..edges.len() {
+        if used[first_edge_index] {
+            continue;
+        }
+        let mut ring = vec![];
+        let mut edge_index = first_edge_index;
+        let closed = loop {
+            used[edge_index] = true;
+            let edge = edges[edge_index];
+            ring.push(edge.p0);
+            if edge.p1 == edges[first_edge_index].p0 {
+                break true;
+            }
+            let back_angle = (edge.p0 - edge.p1).atan2();
+            let next_edge_index = outgoing[&point_key(edge.p1)]
+                .iter()
+                .filter(|&&next_edge_index| !used[next_edge_index])
+                .min_by(|&&index0, &&index1| {
+                    // The clockwise angle from the way back, in (0, 2PI]
+                    let clockwise_angle = |index: usize| {
+                        let angle = back_angle - edges[index].vec().atan2();
+                        if angle <= 0. {
+                            angle + 2. * PI
+                        } else {
+                            angle
+                        }
+                    };
+                    clockwise_angle(index0).partial_cmp(&clockwise_angle(index1)).unwrap()
+                });
+            match next_edge_index {
+                Some(&next_edge_index) => edge_index = next_edge_index,
+                None => break false,
+            }
+        };
+        if closed {
+            rings.push(remove_collinear_vertices(ring));
+        }
+    }
+    rings
+}
+
+fn remove_collinear_vertices(mut ring: Vec<Vec2>) -> Vec<Vec2> {
+    let mut vertex_index = 0;
+    while ring.len() > 3 && vertex_index < ring.len() {
+        let prev = ring[(vertex_index + ring.len() - 1) % ring.len()];
+        let next = ring[(vertex_index + 1) % ring.len()];
+        let vertex = ring[vertex_index];
+        // Only straight continuations, not spikes going back
+        if orient2d(prev, vertex, next) == Sign::Zero && (vertex - prev).dot(next - vertex) > 0. {
+            ring.remove(vertex_index);
+        } else {
+            vertex_index += 1;
+        }
+    }
+    ring
+}
+
+/// Computes a boolean operation between two sets of polygons. The polygons of a set may overlap,
+/// in which case their union is used.
+///
+/// The exteriors of the resulting polygons are in `ShapeWindingOrder::Counterclockwise` order, and
+/// their holes in `ShapeWindingOrder::Clockwise` order.
+pub fn boolean_operation(a: &[Polygon], b: &[Polygon], operation: BooleanOperation) -> Vec<Polygon> {
+    let edges: Vec<Segment> = a
+        .iter()
+        .chain(b)
+        .flat_map(|polygon| polygon.rings())
+        .flat_map(|ring| ring.segments())
+        .filter(|segment| segment.p0 != segment.p1)
+        .collect();
+    let in_a = |point: Vec2| a.iter().any(|polygon| polygon.contains(point));
+    let in_b = |point: Vec2| b.iter().any(|polygon| polygon.contains(point));
+    // An edge is part of the result if the result is on exactly one side of it, which is decided
+    // by looking slightly to the left and to the right of its middle
+    let result_edges: Vec<Segment> = split_edges(&edges)
+        .into_iter()
+        .filter_map(|edge| {
+            let middle = (edge.p0 + edge.p1) / 2.;
+            let offset =
+                left_normal(edge.vec()).normalize() * (edge.vec().magnitude() * 1e-6).max(middle.magnitude() * 1e-12);
+            let (left, right) = (middle + offset, middle - offset);
+            let left_inside = operation.apply(in_a(left), in_b(left));
+            let right_inside = operation.apply(in_a(right), in_b(right));
+            match (left_inside, right_inside) {
+                (true, false) => Some(edge),
+                (false, true) => Some(Segment::new(edge.p1, edge.p0)),
+                _ => None,
+            }
+        })
+        .collect();
+
+    // With the inside on the left, exteriors are counterclockwise in a y-up coordinate system,
+    // which is `ShapeWindingOrder::Clockwise`
+    let (exteriors, holes): (Vec<Shape>, Vec<Shape>) = chain_edges(&result_edges)
+        .into_iter()
+        .map(Shape::new)
+        .partition(|ring| ring.signed_area() < 0.);
+    let mut polygons: Vec<Polygon> = exteriors
+        .into_iter()
+        .map(|mut exterior| {
+            exterior.reverse();
+            Polygon::new(exterior, vec![])
+        })
+        .collect();
+    for mut hole in holes {
+        // A point just inside of the result next to the hole
+        let edge = Segment::new(hole.vertices[0], hole.vertices[1]);
+        let middle = (edge.p0 + edge.p1) / 2.;
+        let point = middle
+            + left_normal(edge.vec()).normalize() * (edge.vec().magnitude() * 1e-6).max(middle.magnitude() * 1e-12);
+        let containing_polygon = polygons
+            .iter_mut()
+            .filter(|polygon| polygon.exterior.contains(point))
+            .min_by(|polygon0, polygon1| polygon0.exterior.area().partial_cmp(&polygon1.exterior.area()).unwrap());
+        if let Some(polygon) = containing_polygon {
+            hole.reverse();
+            polygon.holes.push(hole);
+        }
+    }
+    polygons
+}
+
+impl Polygon {
+    pub fn union(&self, other: &Polygon) -> Vec<Polygon> {
+        boolean_operation(
+            std::slice::from_ref(self),
+            std::slice::from_ref(other),
+            BooleanOperation::Union,
+        )
+    }
+    pub fn intersection(&self, other: &Polygon) -> Vec<Polygon> {
+        boolean_operation(
+            std::slice::from_ref(self),
+            std::slice::from_ref(other),
+            BooleanOperation::Intersection,
+        )
+    }
+    pub fn difference(&self, other: &Polygon) -> Vec<Polygon> {
+        boolean_operation(
+            std::slice::from_ref(self),
+            std::slice::from_ref(other),
+            BooleanOperation::Difference,
+        )
+    }
+    pub fn xor(&self, other: &Polygon) -> Vec<Polygon> {
+        boolean_operation(
+            std::slice::from_ref(self),
+            std::slice::from_ref(other),
+            BooleanOperation::Xor,
+        )
+    }
+}
+
+impl Shape {
+    pub fn union(&self, other: &Shape) -> Vec<Polygon> {
+        Polygon::from(self.clone()).union(&other.clone().into())
+    }
+    pub fn intersection(&self, other: &Shape) -> Vec<Polygon> {
+        Polygon::from(self.clone()).intersection(&other.clone().into())
+    }
+    pub fn difference(&self, other: &Shape) -> Vec<Polygon> {
+        Polygon::from(self.clone()).difference(&other.clone().into())
+    }
+    pub fn xor(&self, other: &Shape) -> Vec<Polygon> {
+        Polygon::from(self.clone()).xor(&other.clone().into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::ShapeWindingOrder;
+
+    fn rectangle(x: f64, y: f64, width: f64, height: f64) -> Shape {
+        Shape::new(vec![
+            Vec2::new(x, y),
+            Vec2::new(x + width, y),
+            Vec2::new(x + width, y + height),
+            Vec2::new(x, y + height),
+        ])
+    }
+
+    fn total_area(polygons: &[Polygon]) -> f64 {
+        polygons.iter().map(Polygon::area).sum()
+    }
+
+    #[test]
+    fn overlapping_squares() {
+        let a = rectangle(0., 0., 10., 10.);
+        let b = rectangle(5., 5., 10., 10.);
+        let union = a.union(&b);
+        assert_eq!(union.len(), 1);
+        assert_eq!(union[0].exterior.vertices.len(), 8);
+        assert_eq!(total_area(&union), 175.);
+        let intersection = a.intersection(&b);
+        assert_eq!(intersection.len(), 1);
+        assert_eq!(intersection[0].exterior.area(), 25.);
+        let difference = a.difference(&b);
+        assert_eq!(difference.len(), 1);
+        assert_eq!(total_area(&difference), 75.);
+        // Two L shapes touching at two corners of the intersection
+        let xor = a.xor(&b);
+        assert_eq!(xor.len(), 2);
+        assert_eq!(total_area(&xor), 150.);
+        for polygon in union.iter().chain(&intersection).chain(&difference).chain(&xor) {
+            assert!(matches!(
+                polygon.exterior.winding_order(),
+                ShapeWindingOrder::Counterclockwise
+            ));
+        }
+    }
+
+    #[test]
+    fn shared_edges() {
+        // Adjacent rooms merge into one, without the vertices in the middle of the merged edges
+        let union = rectangle(0., 0., 10., 10.).union(&rectangle(10., 0., 10., 10.));
+        assert_eq!(union.len(), 1);
+        assert_eq!(total_area(&union), 200.);
+        assert_eq!(union[0].exterior.vertices.len(), 4);
+        assert!(rectangle(0., 0., 10., 10.)
+            .intersection(&rectangle(10., 0., 10., 10.))
+            .is_empty());
+        let difference = rectangle(0., 0., 10., 10.).difference(&rectangle(5., 0., 5., 10.));
+        assert_eq!(difference.len(), 1);
+        assert_eq!(difference[0].exterior.area(), 50.);
+    }
+
+    #[test]
+    fn holes() {
+        let room = rectangle(0., 0., 10., 10.).difference(&rectangle(3., 3., 4., 4.));
+        assert_eq!(room.len(), 1);
+        assert_eq!(room[0].holes.len(), 1);
+        assert!(matches!(room[0].holes[0].winding_order(), ShapeWindingOrder::Clockwise));
+        assert_eq!(total_area(&room), 84.);
+        assert!(!room[0].contains(Vec2::new(5., 5.)));
+        // Filling the hole partially
+        let filled = room[0].union(&rectangle(2., 4., 4., 2.).into());
+        assert_eq!(filled.len(), 1);
+        assert_eq!(total_area(&filled), 90.);
+        assert!(filled[0].contains(Vec2::new(5., 5.)));
+        assert!(!filled[0].contains(Vec2::new(6.5, 5.)));
+    }
+
+    #[test]
+    fn doorway() {
+        // Carving a doorway splits a wall in two
+        let wall = rectangle(0., 0., 20., 1.);
+        let pieces = wall.difference(&rectangle(8., -1., 4., 3.));
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(total_area(&pieces), 16.);
+    }
+}
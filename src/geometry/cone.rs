@@ -0,0 +1,109 @@
+use super::{Point, Scalar, Sign};
+
+/// Whether the rays from `origin` through `p0` and through `p1` point in the same direction.
+pub fn is_same_direction<S: Scalar>(origin: Point<S>, p0: Point<S>, p1: Point<S>) -> bool {
+    S::orient(origin, p0, p1) == Sign::Zero
+        && p0.x.compare(origin.x) == p1.x.compare(origin.x)
+        && p0.y.compare(origin.y) == p1.y.compare(origin.y)
+}
+
+/// `turn(point)` is the sign of the cross product between the direction towards `point` and the
+/// tested direction.
+fn is_in_cone<S: Scalar>(from: Point<S>, vertex: Point<S>, to: Point<S>, turn: impl Fn(Point<S>) -> Sign) -> bool {
+    match S::orient(from, to, vertex) {
+        // Less than half a turn
+        Sign::Positive => turn(from) != Sign::Negative && turn(to) != Sign::Positive,
+        // More than half a turn, so test for the complement instead
+        Sign::Negative => !(turn(to) == Sign::Positive && turn(from) == Sign::Negative),
+        Sign::Zero => is_same_direction(vertex, from, to) || turn(from) != Sign::Negative,
+    }
+}
+
+/// Whether the direction from `vertex` towards `target` lies within the cone swept counterclockwise
+/// (in a y-up coordinate system, clockwise on screen) from the direction towards `from` to the
+/// direction towards `to`. Both boundaries are included, and if `from` and `to` point in the same
+/// direction, the cone is a full turn.
+///
+/// Only orientation tests are used, so the result is exact and there is no wrap-around at any
+/// angle.
+pub fn is_in_vertex_cone<S: Scalar>(from: Point<S>, vertex: Point<S>, to: Point<S>, target: Point<S>) -> bool {
+    is_in_cone(from, vertex, to, |point| S::orient(point, target, vertex))
+}
+
+/// Whether both the direction from `vertex` towards `target` and the opposite direction lie within
+/// the cone, see `is_in_vertex_cone`. If the cone is the outside of a polygon at one of its
+/// vertices, this means that the line through `vertex` and `target` does not enter the polygon
+/// around the vertex.
+pub fn is_tangent_at_vertex<S: Scalar>(from: Point<S>, vertex: Point<S>, to: Point<S>, target: Point<S>) -> bool {
+    is_in_vertex_cone(from, vertex, to, target)
+        && is_in_cone(from, vertex, to, |point| -S::orient(point, target, vertex))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(x: i32, y: i32) -> Point<i32> {
+        Point::new(x, y)
+    }
+
+    #[test]
+    fn vertex_cone() {
+        let vertex = p(0, 0);
+        // A quarter turn from +x to +y
+        assert!(is_in_vertex_cone(p(1, 0), vertex, p(0, 1), p(1, 1)));
+        assert!(is_in_vertex_cone(p(1, 0), vertex, p(0, 1), p(5, 0)));
+        assert!(is_in_vertex_cone(p(1, 0), vertex, p(0, 1), p(0, 5)));
+        assert!(!is_in_vertex_cone(p(1, 0), vertex, p(0, 1), p(-1, 1)));
+        assert!(!is_in_vertex_cone(p(1, 0), vertex, p(0, 1), p(-1, 0)));
+        // Three quarters, from +y to +x
+        assert!(is_in_vertex_cone(p(0, 1), vertex, p(1, 0), p(-1, -1)));
+        assert!(is_in_vertex_cone(p(0, 1), vertex, p(1, 0), p(1, 0)));
+        assert!(!is_in_vertex_cone(p(0, 1), vertex, p(1, 0), p(1, 1)));
+    }
+
+    #[test]
+    fn vertex_cone_wrap_around() {
+        // Crosses the negative x axis, where atan2 jumps from PI to -PI
+        let vertex = p(10, 10);
+        let from = p(0, 11);
+        let to = p(0, 9);
+        assert!(is_in_vertex_cone(from, vertex, to, p(-100, 10)));
+        assert!(is_in_vertex_cone(from, vertex, to, p(-i32::MAX, 11)));
+        assert!(is_in_vertex_cone(from, vertex, to, p(-10, 12)));
+        assert!(!is_in_vertex_cone(from, vertex, to, p(-10, 13)));
+        assert!(!is_in_vertex_cone(from, vertex, to, p(20, 10)));
+    }
+
+    #[test]
+    fn vertex_cone_collinear_neighbours() {
+        // Half a turn, e.g. a vertex in the middle of a straight edge
+        let vertex = p(0, 0);
+        assert!(is_in_vertex_cone(p(1, 0), vertex, p(-1, 0), p(0, 1)));
+        assert!(is_in_vertex_cone(p(1, 0), vertex, p(-1, 0), p(3, 0)));
+        assert!(is_in_vertex_cone(p(1, 0), vertex, p(-1, 0), p(-3, 0)));
+        assert!(!is_in_vertex_cone(p(1, 0), vertex, p(-1, 0), p(0, -1)));
+        // Both lines along the edge touch the polygon only at its boundary
+        assert!(is_tangent_at_vertex(p(1, 0), vertex, p(-1, 0), p(7, 0)));
+        assert!(!is_tangent_at_vertex(p(1, 0), vertex, p(-1, 0), p(0, 1)));
+        // A spike, where both neighbours are in the same direction
+        assert!(is_in_vertex_cone(p(1, 0), vertex, p(2, 0), p(0, -1)));
+    }
+
+    #[test]
+    fn tangent_at_vertex() {
+        // The outside of a square at its bottom left corner (0, 0) in a y-up coordinate system
+        let vertex = p(0, 0);
+        let from = p(0, 1);
+        let to = p(1, 0);
+        assert!(is_tangent_at_vertex(from, vertex, to, p(-1, 1)));
+        assert!(is_tangent_at_vertex(from, vertex, to, p(1, -1)));
+        // Along the edges
+        assert!(is_tangent_at_vertex(from, vertex, to, p(0, -1)));
+        assert!(is_tangent_at_vertex(from, vertex, to, p(3, 0)));
+        // Into the square, or away from it with the opposite direction going into it
+        assert!(!is_tangent_at_vertex(from, vertex, to, p(1, 1)));
+        assert!(!is_tangent_at_vertex(from, vertex, to, p(-1, -1)));
+        assert!(!is_tangent_at_vertex(from, vertex, to, p(-1, -2)));
+    }
+}
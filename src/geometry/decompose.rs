@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+
+use super::{orient2d, Polygon, Shape, Sign};
+
+impl Shape {
+    /// Triangulates a simple (or weakly simple, see `Polygon::to_weakly_simple`) shape by ear
+    /// clipping. Returns triangles as indices into `vertices`, each in
+    /// `ShapeWindingOrder::Counterclockwise` order. Degenerate parts of zero area are left out.
+    pub fn triangulate(&self) -> Vec<[usize; 3]> {
+        let vertices = &self.vertices;
+        // Work counterclockwise in a y-up coordinate system, which is
+        // `ShapeWindingOrder::Clockwise`, so that ears turn left
+        let mut remaining: Vec<usize> = (0..vertices.len()).collect();
+        if self.signed_area() > 0. {
+            remaining.reverse();
+        }
+        let mut triangles = Vec::with_capacity(vertices.len().saturating_sub(2));
+        while remaining.len() >= 3 {
+            let len = remaining.len();
+            let corners = |index: usize| {
+                (
+                    remaining[(index + len - 1) % len],
+                    remaining[index],
+                    remaining[(index + 1) % len],
+                )
+            };
+            let is_ear = |index: usize| {
+                let (prev, vertex, next) = corners(index);
+                let (a, b, c) = (vertices[prev], vertices[vertex], vertices[next]);
+                orient2d(a, b, c) == Sign::Positive
+                    && !remaining.iter().any(|&other| {
+                        let point = vertices[other];
+                        point != a
+                            && point != b
+                            && point != c
+                            && orient2d(a, b, point) != Sign::Negative
+                            && orient2d(b, c, point) != Sign::Negative
+                            && orient2d(c, a, point) != Sign::Negative
+                    })
+            };
+            if let Some(index) = (0..len).find(|&index| is_ear(index)) {
+                let (prev, vertex, next) = corners(index);
+                triangles.push([next, vertex, prev]);
+                remaining.remove(index);
+            } else if let Some(index) = (0..len).find(|&index| {
+                let (prev, vertex, next) = corners(index);
+                orient2d(vertices[prev], vertices[vertex], vertices[next]) == Sign::Zero
+            }) {
+                // Nothing to cut off at a straight angle or a spike
+                remaining.remove(index);
+            } else {
+                // Only possible for shapes that are not simple
+                break;
+            }
+        }
+        triangles
+    }
+    /// Decomposes a simple (or weakly simple) shape into convex parts with the Hertel–Mehlhorn
+    /// algorithm, which merges the triangles of `triangulate` as long as they stay convex. There
+    /// are at most four times as many parts as in an optimal decomposition.
+    ///
+    /// The parts are in `ShapeWindingOrder::Counterclockwise` order.
+    pub fn convex_decomposition(&self) -> Vec<Shape> {
+        let vertices = &self.vertices;
+        // Counterclockwise in a y-up coordinate system, see `triangulate`
+        let mut parts: Vec<Option<Vec<usize>>> = self
+            .triangulate()
+            .into_iter()
+            .map(|[a, b, c]| Some(vec![c, b, a]))
+            .collect();
+        let mut edge_parts: HashMap<(usize, usize), usize> = HashMap::new();
+        for (part_index, part) in parts.iter().enumerate() {
+            let part = part.as_ref().unwrap();
+            for (index, &vertex) in part.iter().enumerate() {
+                edge_parts.insert((vertex, part[(index + 1) % part.len()]), part_index);
+            }
+        }
+        let is_convex_at = |part: &[usize], index: usize| {
+            let len = part.len();
+            orient2d(
+                vertices[part[(index + len - 1) % len]],
+                vertices[part[index]],
+                vertices[part[(index + 1) % len]],
+            ) != Sign::Negative
+        };
+        // Removes diagonals until every remaining one is essential
+        let mut merged_any = true;
+        while merged_any {
+            merged_any = false;
+            for part_index in 0..parts.len() {
+                let mut edge_index = 0;
+                while let Some(part) = &parts[part_index] {
+                    if edge_index >= part.len() {
+                        break;
+                    }
+                    let (a, b) = (part[edge_index], part[(edge_index + 1) % part.len()]);
+                    let other_part_index = match edge_parts.get(&(b, a)) {
+                        Some(&other_part_index) if other_part_index != part_index => other_part_index,
+                        _ => {
+                            edge_index += 1;
+                            continue;
+                        }
+                    };
+                    let other_part = parts[other_part_index].as_ref().unwrap();
+                    // This part from b to a, followed by the other part from a to b
+                    let mut merged: Vec<usize> = part[edge_index + 1..]
+                        .iter()
+                        .chain(&part[..=edge_index])
+                        .copied()
+                        .collect();
+                    let a_index = other_part.iter().position(|&vertex| vertex == a).unwrap();
+                    let other_len = other_part.len();
+                    merged.extend((1..other_len - 1).map(|offset| other_part[(a_index + offset) % other_len]));
+                    let a_merged_index = part.len() - 1;
+                    if is_convex_at(&merged, 0) && is_convex_at(&merged, a_merged_index) {
+                        for (index, &vertex) in merged.iter().enumerate() {
+                            edge_parts.insert((vertex, merged[(index + 1) % merged.len()]), part_index);
+                        }
+                        edge_parts.remove(&(a, b));
+                        edge_parts.remove(&(b, a));
+                        parts[other_part_index] = None;
+                        parts[part_index] = Some(merged);
+                        merged_any = true;
+                        edge_index = 0;
+                    } else {
+                        edge_index += 1;
+                    }
+                }
+            }
+        }
+        parts
+            .into_iter()
+            .flatten()
+            .map(|part| Shape::new(part.into_iter().rev().map(|index| vertices[index]).collect()))
+            .collect()
+    }
+}
+
+impl Polygon {
+    /// Decomposes the polygon into convex parts, see `Shape::convex_decomposition`. Holes are
+    /// connected to the exterior first, see `to_weakly_simple`.
+    pub fn convex_decomposition(&self) -> Vec<Shape> {
+        self.to_weakly_simple().convex_decomposition()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{ShapeWindingOrder, Vec2};
+
+    /// Whether every vertex of `shape` turns the same way, with straight angles allowed.
+    fn is_convex(shape: &Shape) -> bool {
+        (0..shape.vertices.len()).all(|index| {
+            orient2d(
+                shape.prev_vertex(index),
+                shape.vertices[index],
+                shape.next_vertex(index),
+            ) != Sign::Positive
+        })
+    }
+
+    fn l_shape() -> Shape {
+        Shape::new(vec![
+            Vec2::new(0., 0.),
+            Vec2::new(20., 0.),
+            Vec2::new(20., 10.),
+            Vec2::new(10., 10.),
+            Vec2::new(10., 20.),
+            Vec2::new(0., 20.),
+        ])
+    }
+
+    fn total_area(parts: &[Shape]) -> f64 {
+        parts.iter().map(Shape::area).sum()
+    }
+
+    #[test]
+    fn triangulate() {
+        let shape = l_shape();
+        let triangles = shape.triangulate();
+        assert_eq!(triangles.len(), 4);
+        let area: f64 = triangles
+            .iter()
+            .map(|triangle| {
+                let triangle = Shape::new(triangle.iter().map(|&index| shape.vertices[index]).collect());
+                assert!(matches!(triangle.winding_order(), ShapeWindingOrder::Counterclockwise));
+                triangle.area()
+            })
+            .sum();
+        assert_eq!(area, 300.);
+    }
+
+    #[test]
+    fn convex_decomposition() {
+        let parts = l_shape().convex_decomposition();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(total_area(&parts), 300.);
+        for part in &parts {
+            assert!(is_convex(part));
+            assert!(matches!(part.winding_order(), ShapeWindingOrder::Counterclockwise));
+        }
+        // Already convex, with a vertex in the middle of an edge
+        let square = Shape::new(vec![
+            Vec2::new(0., 0.),
+            Vec2::new(5., 0.),
+            Vec2::new(10., 0.),
+            Vec2::new(10., 10.),
+            Vec2::new(0., 10.),
+        ]);
+        assert_eq!(square.convex_decomposition().len(), 1);
+    }
+
+    #[test]
+    fn convex_decomposition_with_hole() {
+        let square = |x: f64, y: f64, size: f64| {
+            Shape::new(vec![
+                Vec2::new(x, y),
+                Vec2::new(x + size, y),
+                Vec2::new(x + size, y + size),
+                Vec2::new(x, y + size),
+            ])
+        };
+        let polygon = Polygon::new(square(0., 0., 10.), vec![square(4., 4., 2.)]);
+        let parts = polygon.convex_decomposition();
+        assert!(parts.len() >= 4);
+        assert_eq!(total_area(&parts), 96.);
+        for part in &parts {
+            assert!(is_convex(part));
+            assert!(!part.contains(Vec2::new(5., 5.)));
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use super::{orient2d, Shape, Sign, Vec2};
+
+impl Shape {
+    /// The smallest convex shape containing all vertices, in `ShapeWindingOrder::Counterclockwise`
+    /// order. Vertices in the middle of a straight hull edge are left out.
+    pub fn convex_hull(&self) -> Shape {
+        let mut points = self.vertices.clone();
+        points.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap().then(a.y.partial_cmp(&b.y).unwrap()));
+        points.dedup();
+        if points.len() < 3 {
+            return Shape::new(points);
+        }
+        // Andrew's monotone chain, building the lower hull (in a y-up coordinate system) from left
+        // to right, and then the upper hull back
+        let mut hull: Vec<Vec2> = Vec::with_capacity(points.len() + 1);
+        let turns_left =
+            |hull: &[Vec2], point: Vec2| orient2d(hull[hull.len() - 2], hull[hull.len() - 1], point) == Sign::Positive;
+        for &point in &points {
+            while hull.len() >= 2 && !turns_left(&hull, point) {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        let lower_hull_len = hull.len() + 1;
+        for &point in points.iter().rev().skip(1) {
+            while hull.len() >= lower_hull_len && !turns_left(&hull, point) {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        // The first point has been added again
+        hull.pop();
+        // Counterclockwise in a y-up coordinate system is `ShapeWindingOrder::Clockwise`
+        hull.reverse();
+        Shape::new(hull)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::ShapeWindingOrder;
+
+    #[test]
+    fn convex_hull() {
+        let shape = Shape::new(vec![
+            Vec2::new(0., 0.),
+            Vec2::new(5., 0.),
+            Vec2::new(10., 0.),
+            Vec2::new(10., 10.),
+            Vec2::new(5., 2.),
+            Vec2::new(0., 10.),
+        ]);
+        let hull = shape.convex_hull();
+        assert!(matches!(hull.winding_order(), ShapeWindingOrder::Counterclockwise));
+        let mut vertices = hull.vertices;
+        vertices.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap().then(a.y.partial_cmp(&b.y).unwrap()));
+        assert_eq!(
+            vertices,
+            vec![
+                Vec2::new(0., 0.),
+                Vec2::new(0., 10.),
+                Vec2::new(10., 0.),
+                Vec2::new(10., 10.)
+            ]
+        );
+        assert_eq!(
+            Shape::new(vec![Vec2::new(1., 1.)]).convex_hull().vertices,
+            vec![Vec2::new(1., 1.)]
+        );
+    }
+}
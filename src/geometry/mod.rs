@@ -1,9 +1,28 @@
+mod aabb;
 mod angle;
+mod boolean;
+mod cone;
+mod decompose;
+mod hull;
+mod polygon;
+mod predicates;
+mod scalar;
 mod segment;
 mod shape;
+mod simplify;
+mod transform;
 mod vec2;
+mod visibility;
 
+pub use aabb::*;
 pub use angle::*;
+pub use boolean::*;
+pub use cone::*;
+pub use polygon::*;
+pub use predicates::*;
+pub use scalar::*;
 pub use segment::*;
 pub use shape::*;
+pub use transform::*;
 pub use vec2::*;
+pub use visibility::*;
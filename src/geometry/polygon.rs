@@ -0,0 +1,134 @@
+use super::{Segment, Shape, ShapeWindingOrder, Vec2};
+
+/// A shape with holes. A point is inside of the polygon if it is inside of the exterior and not
+/// inside of any hole.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    pub exterior: Shape,
+    pub holes: Vec<Shape>,
+}
+
+impl Polygon {
+    pub fn new(exterior: Shape, holes: Vec<Shape>) -> Self {
+        Polygon { exterior, holes }
+    }
+    pub fn rings(&self) -> impl Iterator<Item = &Shape> {
+        std::iter::once(&self.exterior).chain(self.holes.iter())
+    }
+    /// Tests whether `point` lies inside the polygon using the even-odd rule over all rings. Points
+    /// on the boundary may be reported either way.
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.rings().filter(|ring| ring.contains(point)).count() % 2 == 1
+    }
+    pub fn area(&self) -> f64 {
+        self.exterior.area() - self.holes.iter().map(Shape::area).sum::<f64>()
+    }
+    /// Converts the polygon into a single weakly simple shape, by cutting a zero-width channel
+    /// from the exterior to each hole. The result can be passed to `NavigationObstacle::new`, the
+    /// channels cannot be passed through.
+    ///
+    /// The exterior is in `ShapeWindingOrder::Counterclockwise` order, and the holes are walked in
+    /// the opposite direction.
+    pub fn to_weakly_simple(&self) -> Shape {
+        let mut ring = oriented(&self.exterior, true);
+        let mut remaining_holes: Vec<Vec<Vec2>> = self
+            .holes
+            .iter()
+            .filter(|hole| hole.vertices.len() >= 3)
+            .map(|hole| oriented(hole, false))
+            .collect();
+        while !remaining_holes.is_empty() {
+            // Connect the hole that is closest to the ring, using the shortest channel that does
+            // not cross any edge
+            let mut best_channel: Option<(f64, usize, usize, usize)> = None;
+            for (hole_index, hole) in remaining_holes.iter().enumerate() {
+                for (ring_vertex_index, &ring_vertex) in ring.iter().enumerate() {
+                    for (hole_vertex_index, &hole_vertex) in hole.iter().enumerate() {
+                        let dist_squared = ring_vertex.dist_squared(hole_vertex);
+                        if matches!(best_channel, Some((best_dist_squared, ..)) if best_dist_squared <= dist_squared) {
+                            continue;
+                        }
+                        if self.is_valid_channel(Segment::new(ring_vertex, hole_vertex), &ring, &remaining_holes) {
+                            best_channel = Some((dist_squared, hole_index, ring_vertex_index, hole_vertex_index));
+                        }
+                    }
+                }
+            }
+            let (_, hole_index, ring_vertex_index, hole_vertex_index) = match best_channel {
+                Some(best_channel) => best_channel,
+                // Only possible for invalid polygons, e.g. with holes outside of the exterior
+                None => break,
+            };
+            let hole = remaining_holes.remove(hole_index);
+            let mut new_ring = Vec::with_capacity(ring.len() + hole.len() + 2);
+            new_ring.extend_from_slice(&ring[..=ring_vertex_index]);
+            new_ring.extend_from_slice(&hole[hole_vertex_index..]);
+            new_ring.extend_from_slice(&hole[..=hole_vertex_index]);
+            new_ring.extend_from_slice(&ring[ring_vertex_index..]);
+            ring = new_ring;
+        }
+        Shape::new(ring)
+    }
+    fn is_valid_channel(&self, channel: Segment, ring: &[Vec2], holes: &[Vec<Vec2>]) -> bool {
+        let crosses = |vertices: &[Vec2]| {
+            Shape::new(vertices.to_vec())
+                .segments()
+                .into_iter()
+                .any(|segment| channel.connective_intersect(&segment))
+        };
+        // Without crossing any edge, the whole channel is either inside or outside of the polygon
+        !crosses(ring) && !holes.iter().any(|hole| crosses(hole)) && self.contains((channel.p0 + channel.p1) / 2.)
+    }
+}
+
+impl From<Shape> for Polygon {
+    fn from(shape: Shape) -> Self {
+        Polygon::new(shape, vec![])
+    }
+}
+
+fn oriented(shape: &Shape, counterclockwise: bool) -> Vec<Vec2> {
+    let mut vertices = shape.vertices.clone();
+    let is_counterclockwise = matches!(shape.winding_order(), ShapeWindingOrder::Counterclockwise);
+    if is_counterclockwise != counterclockwise {
+        vertices.reverse();
+    }
+    vertices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f64, y: f64, size: f64) -> Shape {
+        Shape::new(vec![
+            Vec2::new(x, y),
+            Vec2::new(x + size, y),
+            Vec2::new(x + size, y + size),
+            Vec2::new(x, y + size),
+        ])
+    }
+
+    #[test]
+    fn polygon_contains() {
+        let polygon = Polygon::new(square(0., 0., 10.), vec![square(2., 2., 2.), square(6., 6., 2.)]);
+        assert!(polygon.contains(Vec2::new(1., 1.)));
+        assert!(!polygon.contains(Vec2::new(3., 3.)));
+        assert!(!polygon.contains(Vec2::new(7., 7.)));
+        assert!(!polygon.contains(Vec2::new(11., 1.)));
+        assert_eq!(polygon.area(), 92.);
+    }
+
+    #[test]
+    fn weakly_simple() {
+        let polygon = Polygon::new(square(0., 0., 10.), vec![square(2., 2., 2.), square(6., 6., 2.)]);
+        let shape = polygon.to_weakly_simple();
+        // Two extra vertices for each channel
+        assert_eq!(shape.vertices.len(), 4 + 2 * 6);
+        assert!(matches!(shape.winding_order(), ShapeWindingOrder::Counterclockwise));
+        assert_eq!(shape.area(), 92.);
+        assert!(shape.contains(Vec2::new(1., 1.)));
+        assert!(!shape.contains(Vec2::new(3., 3.)));
+        assert!(!shape.contains(Vec2::new(7., 7.)));
+    }
+}
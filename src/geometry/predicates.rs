@@ -0,0 +1,228 @@
+//! Robust geometric predicates.
+//!
+//! The predicates first evaluate their determinant with plain floating point arithmetic, and only
+//! if the result is too close to zero to be trusted, they recompute it exactly with floating point
+//! expansions (see Shewchuk, "Adaptive Precision Floating-Point Arithmetic and Fast Robust
+//! Geometric Predicates"). The returned signs are therefore exact regardless of the scale of the
+//! coordinates, as long as the intermediate products neither overflow nor underflow, which holds
+//! for coordinate differences roughly between 1e-150 and 1e150.
+
+use super::{Sign, Vec2};
+
+/// Half an ulp of 1, i.e. the relative rounding error of a single operation.
+const EPSILON: f64 = f64::EPSILON / 2.;
+/// 2^27 + 1, used to split a double into two halves with 26 significant bits each.
+const SPLITTER: f64 = 134_217_729.;
+const ORIENT2D_ERROR_BOUND: f64 = (3. + 16. * EPSILON) * EPSILON;
+const INCIRCLE_ERROR_BOUND: f64 = (10. + 96. * EPSILON) * EPSILON;
+
+/// `a + b` as a non-overlapping pair `(high, low)` with `high + low == a + b` exactly.
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let x = a + b;
+    let b_virtual = x - a;
+    let a_virtual = x - b_virtual;
+    let b_roundoff = b - b_virtual;
+    let a_roundoff = a - a_virtual;
+    (x, a_roundoff + b_roundoff)
+}
+
+fn split(a: f64) -> (f64, f64) {
+    let c = SPLITTER * a;
+    let a_big = c - a;
+    let high = c - a_big;
+    (high, a - high)
+}
+
+/// `a * b` as a non-overlapping pair `(high, low)` with `high + low == a * b` exactly.
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let x = a * b;
+    let (a_high, a_low) = split(a);
+    let (b_high, b_low) = split(b);
+    let error1 = x - a_high * b_high;
+    let error2 = error1 - a_low * b_high;
+    let error3 = error2 - a_high * b_low;
+    (x, a_low * b_low - error3)
+}
+
+/// An exact sum of non-overlapping doubles, ordered by increasing magnitude.
+#[derive(Debug, Clone)]
+struct Expansion(Vec<f64>);
+
+impl Expansion {
+    fn from_pair((high, low): (f64, f64)) -> Self {
+        Expansion(vec![low, high])
+    }
+    fn add_scalar(&self, b: f64) -> Self {
+        let mut components = Vec::with_capacity(self.0.len() + 1);
+        let mut q = b;
+        for &component in &self.0 {
+            let (sum, error) = two_sum(q, component);
+            if error != 0. {
+                components.push(error);
+            }
+            q = sum;
+        }
+        components.push(q);
+        Expansion(components)
+    }
+    fn add(&self, other: &Expansion) -> Self {
+        other
+            .0
+            .iter()
+            .fold(self.clone(), |sum, &component| sum.add_scalar(component))
+    }
+    fn negate(&self) -> Self {
+        Expansion(self.0.iter().map(|component| -component).collect())
+    }
+    fn scale(&self, b: f64) -> Self {
+        self.0.iter().fold(Expansion(vec![]), |sum, &component| {
+            sum.add(&Expansion::from_pair(two_product(component, b)))
+        })
+    }
+    fn mul(&self, other: &Expansion) -> Self {
+        other.0.iter().fold(Expansion(vec![]), |product, &component| {
+            product.add(&self.scale(component))
+        })
+    }
+    fn sign(&self) -> Sign {
+        // The component with the largest magnitude decides the sign
+        self.0
+            .iter()
+            .rev()
+            .find(|&&component| component != 0.)
+            .map_or(Sign::Zero, |&component| Sign::of(component))
+    }
+}
+
+fn exact_diff(a: f64, b: f64) -> Expansion {
+    Expansion::from_pair(two_sum(a, -b))
+}
+
+/// The sign of the cross product `(a - c) × (b - c)`: `Positive` if `a`, `b` and `c` are in
+/// counterclockwise order in a y-up coordinate system (clockwise on screen), `Negative` for the
+/// opposite order and `Zero` if they are collinear.
+pub fn orient2d(a: Vec2, b: Vec2, c: Vec2) -> Sign {
+    let det_left = (a.x - c.x) * (b.y - c.y);
+    let det_right = (a.y - c.y) * (b.x - c.x);
+    let det = det_left - det_right;
+    let error_bound = ORIENT2D_ERROR_BOUND * (det_left.abs() + det_right.abs());
+    if det > error_bound || -det > error_bound {
+        return Sign::of(det);
+    }
+    exact_diff(a.x, c.x)
+        .mul(&exact_diff(b.y, c.y))
+        .add(&exact_diff(a.y, c.y).mul(&exact_diff(b.x, c.x)).negate())
+        .sign()
+}
+
+/// Whether `d` lies inside the circle through `a`, `b` and `c`, which have to be in
+/// counterclockwise order in a y-up coordinate system (see `orient2d`): `Positive` if inside,
+/// `Negative` if outside and `Zero` if on the circle.
+pub fn incircle(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> Sign {
+    let (adx, ady) = (a.x - d.x, a.y - d.y);
+    let (bdx, bdy) = (b.x - d.x, b.y - d.y);
+    let (cdx, cdy) = (c.x - d.x, c.y - d.y);
+    let (bdx_cdy, cdx_bdy) = (bdx * cdy, cdx * bdy);
+    let (cdx_ady, adx_cdy) = (cdx * ady, adx * cdy);
+    let (adx_bdy, bdx_ady) = (adx * bdy, bdx * ady);
+    let a_lift = adx * adx + ady * ady;
+    let b_lift = bdx * bdx + bdy * bdy;
+    let c_lift = cdx * cdx + cdy * cdy;
+    let det = a_lift * (bdx_cdy - cdx_bdy) + b_lift * (cdx_ady - adx_cdy) + c_lift * (adx_bdy - bdx_ady);
+    let permanent = (bdx_cdy.abs() + cdx_bdy.abs()) * a_lift
+        + (cdx_ady.abs() + adx_cdy.abs()) * b_lift
+        + (adx_bdy.abs() + bdx_ady.abs()) * c_lift;
+    let error_bound = INCIRCLE_ERROR_BOUND * permanent;
+    if det > error_bound || -det > error_bound {
+        return Sign::of(det);
+    }
+    let (adx, ady) = (exact_diff(a.x, d.x), exact_diff(a.y, d.y));
+    let (bdx, bdy) = (exact_diff(b.x, d.x), exact_diff(b.y, d.y));
+    let (cdx, cdy) = (exact_diff(c.x, d.x), exact_diff(c.y, d.y));
+    let cross = |x0: &Expansion, y0: &Expansion, x1: &Expansion, y1: &Expansion| x0.mul(y1).add(&y0.mul(x1).negate());
+    let lift = |x: &Expansion, y: &Expansion| x.mul(x).add(&y.mul(y));
+    lift(&adx, &ady)
+        .mul(&cross(&bdx, &bdy, &cdx, &cdy))
+        .add(&lift(&bdx, &bdy).mul(&cross(&cdx, &cdy, &adx, &ady)))
+        .add(&lift(&cdx, &cdy).mul(&cross(&adx, &ady, &bdx, &bdy)))
+        .sign()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orient2d_simple() {
+        let a = Vec2::new(0., 0.);
+        let b = Vec2::new(1., 0.);
+        assert_eq!(orient2d(a, b, Vec2::new(0., 1.)), Sign::Positive);
+        assert_eq!(orient2d(a, b, Vec2::new(0., -1.)), Sign::Negative);
+        assert_eq!(orient2d(a, b, Vec2::new(5., 0.)), Sign::Zero);
+        assert_eq!(orient2d(a, a, Vec2::new(5., 3.)), Sign::Zero);
+    }
+
+    #[test]
+    fn orient2d_near_collinear() {
+        // The points are collinear exactly when i == j, which naive evaluation gets wrong for many
+        // of them
+        let a = Vec2::new(12., 12.);
+        let b = Vec2::new(24., 24.);
+        let ulp = f64::EPSILON / 2.;
+        for i in 0..32 {
+            for j in 0..32 {
+                let c = Vec2::new(0.5 + i as f64 * ulp, 0.5 + j as f64 * ulp);
+                let expected = if i < j {
+                    Sign::Positive
+                } else if i > j {
+                    Sign::Negative
+                } else {
+                    Sign::Zero
+                };
+                assert_eq!(orient2d(a, b, c), expected, "i = {}, j = {}", i, j);
+            }
+        }
+    }
+
+    #[test]
+    fn orient2d_scale_invariant() {
+        // Powers of two, so that scaling does not round
+        for &exponent in &[-400, -60, 0, 60, 400] {
+            let scale = 2f64.powi(exponent);
+            let a = Vec2::new(1., 1.) * scale;
+            let b = Vec2::new(3., 2.) * scale;
+            assert_eq!(orient2d(a, b, Vec2::new(5., 3.) * scale), Sign::Zero);
+            assert_eq!(
+                orient2d(a, b, Vec2::new(5., 3.000_000_000_000_001) * scale),
+                Sign::Positive
+            );
+            assert_eq!(
+                orient2d(a, b, Vec2::new(5., 2.999_999_999_999_999) * scale),
+                Sign::Negative
+            );
+        }
+    }
+
+    #[test]
+    fn orient2d_large_offset() {
+        let offset = Vec2::new(1e15, -1e15);
+        let a = offset + Vec2::new(0., 0.);
+        let b = offset + Vec2::new(4., 2.);
+        assert_eq!(orient2d(a, b, offset + Vec2::new(2., 1.)), Sign::Zero);
+        assert_eq!(orient2d(a, b, offset + Vec2::new(2., 1.125)), Sign::Positive);
+        assert_eq!(orient2d(a, b, offset + Vec2::new(2., 0.875)), Sign::Negative);
+    }
+
+    #[test]
+    fn incircle_cocircular() {
+        // Points on the circle around (1, 1) with radius 5, offset far from the origin
+        let offset = Vec2::new(1e9, 1e9);
+        let a = offset + Vec2::new(6., 1.);
+        let b = offset + Vec2::new(4., 5.);
+        let c = offset + Vec2::new(-4., 1.);
+        assert_eq!(incircle(a, b, c, offset + Vec2::new(1., -4.)), Sign::Zero);
+        assert_eq!(incircle(a, b, c, offset + Vec2::new(1., 1.)), Sign::Positive);
+        assert_eq!(incircle(a, b, c, offset + Vec2::new(1., -4.000_001)), Sign::Negative);
+        assert_eq!(incircle(a, b, c, offset + Vec2::new(1., -3.999_999)), Sign::Positive);
+    }
+}
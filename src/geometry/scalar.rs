@@ -0,0 +1,216 @@
+use std::cmp::Ordering;
+use std::fmt::Debug;
+
+use super::{orient2d, Sign, Vec2};
+
+/// A coordinate type that geometric predicates can be evaluated on exactly.
+///
+/// The predicates only use operations whose results are fully specified (integer arithmetic, or
+/// correctly rounded IEEE 754 operations), so they give bit-identical results on every platform.
+/// Notably, no trigonometric functions are involved.
+pub trait Scalar: Copy + PartialOrd + Debug {
+    /// The sign of the cross product `(a - c) × (b - c)`, see `orient2d`.
+    fn orient(a: Point<Self>, b: Point<Self>, c: Point<Self>) -> Sign;
+    /// The euclidean distance between two points, only used for path costs.
+    fn distance(a: Point<Self>, b: Point<Self>) -> f64;
+    /// Total order on the coordinates, treating incomparable values (NaN) as equal.
+    fn compare(self, other: Self) -> Ordering {
+        self.partial_cmp(&other).unwrap_or(Ordering::Equal)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Point<S> {
+    pub x: S,
+    pub y: S,
+}
+
+impl<S: Scalar> Point<S> {
+    pub fn new(x: S, y: S) -> Self {
+        Point { x, y }
+    }
+    pub fn dist(self, other: Self) -> f64 {
+        S::distance(self, other)
+    }
+}
+
+impl From<Vec2> for Point<f64> {
+    fn from(vec: Vec2) -> Self {
+        Point { x: vec.x, y: vec.y }
+    }
+}
+
+impl From<Point<f64>> for Vec2 {
+    fn from(point: Point<f64>) -> Self {
+        Vec2::new(point.x, point.y)
+    }
+}
+
+impl Scalar for f64 {
+    fn orient(a: Point<Self>, b: Point<Self>, c: Point<Self>) -> Sign {
+        orient2d(a.into(), b.into(), c.into())
+    }
+    fn distance(a: Point<Self>, b: Point<Self>) -> f64 {
+        let dx = a.x - b.x;
+        let dy = a.y - b.y;
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+fn sign_of_wide(value: i128) -> Sign {
+    match value.cmp(&0) {
+        Ordering::Less => Sign::Negative,
+        Ordering::Equal => Sign::Zero,
+        Ordering::Greater => Sign::Positive,
+    }
+}
+
+fn wide_cross(a: (i128, i128), b: (i128, i128), c: (i128, i128)) -> i128 {
+    (a.0 - c.0) * (b.1 - c.1) - (a.1 - c.1) * (b.0 - c.0)
+}
+
+fn wide_distance_squared(a: (i128, i128), b: (i128, i128)) -> f64 {
+    let dx = a.0 - b.0;
+    let dy = a.1 - b.1;
+    // Integer to float conversion is correctly rounded
+    (dx * dx + dy * dy) as f64
+}
+
+impl Scalar for i32 {
+    fn orient(a: Point<Self>, b: Point<Self>, c: Point<Self>) -> Sign {
+        sign_of_wide(wide_cross(a.widen(), b.widen(), c.widen()))
+    }
+    fn distance(a: Point<Self>, b: Point<Self>) -> f64 {
+        wide_distance_squared(a.widen(), b.widen()).sqrt()
+    }
+}
+
+/// # Important
+/// The cross products are computed with `i128`, so coordinates have to satisfy `|x| < 2^62`.
+impl Scalar for i64 {
+    fn orient(a: Point<Self>, b: Point<Self>, c: Point<Self>) -> Sign {
+        sign_of_wide(wide_cross(a.widen(), b.widen(), c.widen()))
+    }
+    fn distance(a: Point<Self>, b: Point<Self>) -> f64 {
+        wide_distance_squared(a.widen(), b.widen()).sqrt()
+    }
+}
+
+/// A fixed-point number with `Fixed::FRACTION_BITS` fractional bits.
+///
+/// # Important
+/// The cross products are computed with `i128`, so the raw values have to satisfy `|x| < 2^62`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Fixed(i64);
+
+impl Fixed {
+    pub const FRACTION_BITS: u32 = 16;
+    pub const ONE: Fixed = Fixed(1 << Fixed::FRACTION_BITS);
+
+    pub fn from_raw(raw: i64) -> Self {
+        Fixed(raw)
+    }
+    pub fn from_int(value: i32) -> Self {
+        Fixed(i64::from(value) << Fixed::FRACTION_BITS)
+    }
+    /// Rounds `value` to the nearest representable number. Only deterministic if all clients use
+    /// the same `value`, so use it for loading data, not for simulation.
+    pub fn from_f64(value: f64) -> Self {
+        Fixed((value * Fixed::ONE.0 as f64).round() as i64)
+    }
+    pub fn raw(self) -> i64 {
+        self.0
+    }
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / Fixed::ONE.0 as f64
+    }
+}
+
+impl Scalar for Fixed {
+    fn orient(a: Point<Self>, b: Point<Self>, c: Point<Self>) -> Sign {
+        // Scaling all coordinates by the same factor does not change the sign
+        sign_of_wide(wide_cross(a.widen(), b.widen(), c.widen()))
+    }
+    fn distance(a: Point<Self>, b: Point<Self>) -> f64 {
+        wide_distance_squared(a.widen(), b.widen()).sqrt() / Fixed::ONE.0 as f64
+    }
+}
+
+trait Widen {
+    fn widen(self) -> (i128, i128);
+}
+
+/// Differences of such values are below `2^63`, and the cross product of two differences stays
+/// below `2^127`, which fits into an `i128`.
+fn debug_assert_widenable(value: i64) {
+    debug_assert!(
+        value.unsigned_abs() < 1 << 62,
+        "{} is out of the range of exact predicates",
+        value
+    );
+}
+
+impl Widen for Point<i32> {
+    fn widen(self) -> (i128, i128) {
+        (self.x.into(), self.y.into())
+    }
+}
+
+impl Widen for Point<i64> {
+    fn widen(self) -> (i128, i128) {
+        debug_assert_widenable(self.x);
+        debug_assert_widenable(self.y);
+        (self.x.into(), self.y.into())
+    }
+}
+
+impl Widen for Point<Fixed> {
+    fn widen(self) -> (i128, i128) {
+        debug_assert_widenable(self.x.0);
+        debug_assert_widenable(self.y.0);
+        (self.x.0.into(), self.y.0.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_orient() {
+        let a = Point::new(-(1i64 << 61), -1);
+        let b = Point::new(0i64, 0);
+        assert_eq!(i64::orient(a, b, Point::new(0, 1)), Sign::Positive);
+        assert_eq!(i64::orient(a, b, Point::new(0, -1)), Sign::Negative);
+        assert_eq!(i64::orient(a, b, Point::new(1 << 61, 1)), Sign::Zero);
+        assert_eq!(
+            i32::orient(
+                Point::new(i32::MIN, i32::MIN),
+                Point::new(i32::MAX, i32::MAX),
+                Point::new(0, 1)
+            ),
+            Sign::Positive
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic]
+    fn integer_orient_out_of_range() {
+        let a = Point::new(-(1i64 << 62), 0);
+        i64::orient(a, Point::new(1 << 61, 1), Point::new(0, 0));
+    }
+
+    #[test]
+    fn fixed() {
+        assert_eq!(Fixed::from_int(3).to_f64(), 3.);
+        assert_eq!(Fixed::from_f64(-1.5), Fixed::from_raw(-3 << (Fixed::FRACTION_BITS - 1)));
+        let a = Point::new(Fixed::from_int(0), Fixed::from_int(0));
+        let b = Point::new(Fixed::from_int(3), Fixed::from_int(4));
+        assert_eq!(a.dist(b), 5.);
+        assert_eq!(
+            Fixed::orient(a, b, Point::new(Fixed::from_f64(1.5), Fixed::from_int(2))),
+            Sign::Zero
+        );
+    }
+}
@@ -1,17 +1,13 @@
-use super::Vec2;
-use crate::geometry::Sign;
+use super::{orient2d, Sign, Vec2};
 
 pub trait Linear {
     fn p0(&self) -> Vec2;
     fn p1(&self) -> Vec2;
     fn is_horizontal(&self) -> bool {
-        (self.p0().y - self.p1().y).abs() <= f64::EPSILON
+        self.p0().y == self.p1().y
     }
     fn is_vertical(&self) -> bool {
-        (self.p0().x - self.p1().x).abs() <= f64::EPSILON
-    }
-    fn slope(&self) -> f64 {
-        (self.p1().y - self.p0().y) / (self.p1().x - self.p0().x)
+        self.p0().x == self.p1().x
     }
 }
 
@@ -63,22 +59,6 @@ fn contains<T: PartialOrd>(b0: T, b1: T, value: T) -> bool {
     min <= value && value <= max
 }
 
-impl Segment {
-    #[inline]
-    fn find_intersection_with_segment_only_other_vertical(&self, other: &Segment) -> Option<Vec2> {
-        if contains(self.p0.x, self.p1.x, other.p0.x) {
-            let intersect_y = self.p0.y + (other.p0.x - self.p0.x) * self.slope();
-            if contains(other.p0.y, other.p1.y, intersect_y) {
-                Some(Vec2::new(other.p0.x, intersect_y))
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    }
-}
-
 pub trait IntersectSegment {
     fn intersect_segment(&self, other: &Segment) -> Option<Vec2>;
 }
@@ -92,131 +72,113 @@ impl Segment {
     }
 }
 
+impl Segment {
+    /// Finds the intersection between two segments lying on the same line.
+    #[inline]
+    fn find_intersection_with_collinear_segment(&self, other: &Segment) -> Option<Vec2> {
+        // Compare the points along the axis the segments extend the most in
+        let points = [self.p0, self.p1, other.p0, other.p1];
+        let extent = |axis: fn(&Vec2) -> f64| {
+            let min = points.iter().map(axis).fold(f64::INFINITY, f64::min);
+            let max = points.iter().map(axis).fold(f64::NEG_INFINITY, f64::max);
+            max - min
+        };
+        let use_x = extent(|point| point.x) >= extent(|point| point.y);
+        let key = |point: Vec2| if use_x { point.x } else { point.y };
+
+        // Test for overlap
+        let (self_min, self_max) = min_max(key(self.p0), key(self.p1));
+        let (other_min, other_max) = min_max(key(other.p0), key(other.p1));
+        if self_max < other_min || other_max < self_min {
+            return None;
+        }
+
+        // Use the point that is closer to self.p0
+        if contains(other_min, other_max, key(self.p0)) {
+            Some(self.p0)
+        } else if (key(other.p0) - key(self.p0)).abs() < (key(other.p1) - key(self.p0)).abs() {
+            Some(other.p0)
+        } else {
+            Some(other.p1)
+        }
+    }
+}
+
 impl IntersectSegment for Segment {
-    /// Finds the intersection between self and a given segment.
+    /// Finds the intersection between self and a given segment. If the segments overlap, the
+    /// intersection closest to `self.p0` is returned.
+    ///
+    /// Whether the segments intersect is decided exactly with `orient2d`, only the position of a
+    /// proper crossing is subject to rounding.
     fn intersect_segment(&self, other: &Segment) -> Option<Vec2> {
-        if self.is_vertical() {
-            let self_x = self.p0.x;
-            // Slope cannot be used
-            if other.is_vertical() {
-                if (self_x - other.p0.x).abs() >= f64::EPSILON {
-                    return None;
-                }
-
-                // Vertical collinear
-                let (min_y, max_y) = min_max(other.p0.y, other.p1.y);
-
-                // Test for overlap
-                if self.p0.y < min_y && self.p1.y < min_y {
-                    return None;
-                }
-                if self.p0.y > max_y && self.p1.y > max_y {
-                    return None;
-                }
-
-                // Use the point that is closer to self.p0
-                if self.p0.y < min_y {
-                    if other.p0.y < other.p1.y {
-                        Some(other.p0)
-                    } else {
-                        Some(other.p1)
-                    }
-                } else if self.p0.y > max_y {
-                    if other.p0.y < other.p1.y {
-                        Some(other.p1)
-                    } else {
-                        Some(other.p0)
-                    }
-                } else {
-                    Some(self.p0)
-                }
-            } else {
-                // Guaranteed that `other` is not vertical
-                other.find_intersection_with_segment_only_other_vertical(self)
-            }
-        } else if other.is_vertical() {
-            self.find_intersection_with_segment_only_other_vertical(other)
+        let self_p0_side = orient2d(self.p0, self.p1, other.p0);
+        let self_p1_side = orient2d(self.p0, self.p1, other.p1);
+        let other_p0_side = orient2d(other.p0, other.p1, self.p0);
+        let other_p1_side = orient2d(other.p0, other.p1, self.p1);
+        if self_p0_side == Sign::Zero
+            && self_p1_side == Sign::Zero
+            && other_p0_side == Sign::Zero
+            && other_p1_side == Sign::Zero
+        {
+            // Collinear, or at least one of them is a point
+            return self.find_intersection_with_collinear_segment(other);
+        }
+        if (self_p0_side != Sign::Zero && self_p0_side == self_p1_side)
+            || (other_p0_side != Sign::Zero && other_p0_side == other_p1_side)
+        {
+            // Both endpoints of one segment are on the same side of the other
+            return None;
+        }
+        if other_p0_side == Sign::Zero {
+            Some(self.p0)
+        } else if other_p1_side == Sign::Zero {
+            Some(self.p1)
+        } else if self_p0_side == Sign::Zero {
+            Some(other.p0)
+        } else if self_p1_side == Sign::Zero {
+            Some(other.p1)
         } else {
-            let self_slope = self.slope();
-            let other_slope = other.slope();
-            if (self_slope - other_slope).abs() <= f64::EPSILON {
-                let other_p0_y_interpolate_to_self_p0_x = other.p0.y - self_slope * (other.p0.x - self.p0.x);
-                if (other_p0_y_interpolate_to_self_p0_x - self.p0.y).abs() >= f64::EPSILON {
-                    // Parallel
-                    None
-                } else {
-                    // Non-vertical collinear
-                    let (min_x, max_x) = min_max(other.p0.x, other.p1.x);
-
-                    // Test for overlap
-                    if self.p0.x < min_x && self.p1.x < min_x {
-                        return None;
-                    }
-                    if self.p0.x > max_x && self.p1.x > max_x {
-                        return None;
-                    }
-
-                    // Use the point that is closer to self.p0
-                    if self.p0.x < min_x {
-                        if other.p0.x < other.p1.x {
-                            Some(other.p0)
-                        } else {
-                            Some(other.p1)
-                        }
-                    } else if self.p0.x > max_x {
-                        if other.p0.x < other.p1.x {
-                            Some(other.p1)
-                        } else {
-                            Some(other.p0)
-                        }
-                    } else {
-                        Some(self.p0)
-                    }
-                }
-            } else {
-                let self_p0_y_interpolate_to_other_p0_x = self.p0.y + self_slope * (other.p0.x - self.p0.x);
-                let slope_diff = self_slope - other_slope; // How fast self catches up
-                let intersect_x = other.p0.x + (other.p0.y - self_p0_y_interpolate_to_other_p0_x) / slope_diff;
-                if contains(self.p0.x, self.p1.x, intersect_x) && contains(other.p0.x, other.p1.x, intersect_x) {
-                    Some(Vec2::new(
-                        intersect_x,
-                        self.p0.y + self_slope * (intersect_x - self.p0.x),
-                    ))
-                } else {
-                    None
-                }
-            }
+            // Proper crossing
+            let self_vec = self.vec();
+            let other_vec = other.vec();
+            let t = (other.p0 - self.p0).cross(other_vec) / self_vec.cross(other_vec);
+            Some(self.p0 + self_vec * t.clamp(0., 1.))
         }
     }
 }
 
 impl Segment {
     pub fn connective_intersect(&self, other: &Segment) -> bool {
-        if self.p0 == other.p0 || self.p1 == other.p1 {
-            overlaps(self.vec(), other.vec())
-        } else if self.p0 == other.p1 || self.p1 == other.p0 {
-            overlaps(self.vec(), other.vec_rev())
+        if self.p0 == other.p0 {
+            overlaps(self.p0, self.p1, other.p1)
+        } else if self.p1 == other.p1 {
+            overlaps(self.p1, self.p0, other.p0)
+        } else if self.p0 == other.p1 {
+            overlaps(self.p0, self.p1, other.p0)
+        } else if self.p1 == other.p0 {
+            overlaps(self.p1, self.p0, other.p1)
         } else {
             self.intersect(other).is_some()
         }
     }
 }
 
-fn overlaps(vec_self: Vec2, vec_target: Vec2) -> bool {
-    if vec_self.cross(vec_target).abs() <= f64::EPSILON {
-        let self_sign_x = vec_self.sign_x();
-        let target_sign_x = vec_target.sign_x();
-        if self_sign_x == target_sign_x {
-            if self_sign_x == Sign::Zero {
-                let self_sign_y = vec_self.sign_y();
-                let target_sign_y = vec_target.sign_y();
-                self_sign_y == target_sign_y
+/// Whether the rays from `origin` through `p0` and through `p1` point in the same direction.
+fn overlaps(origin: Vec2, p0: Vec2, p1: Vec2) -> bool {
+    if orient2d(origin, p0, p1) == Sign::Zero {
+        // Rounding never changes the sign of a difference
+        let vec0 = p0 - origin;
+        let vec1 = p1 - origin;
+        let sign0_x = vec0.sign_x();
+        let sign1_x = vec1.sign_x();
+        if sign0_x == sign1_x {
+            if sign0_x == Sign::Zero {
+                vec0.sign_y() == vec1.sign_y()
             } else {
                 true
             }
         } else {
             false
-            // target_sign_x == Sign::Zero && vec_target.sign_y() == Sign::Zero
         }
     } else {
         false
@@ -231,11 +193,52 @@ impl Segment {
         self.p0 - self.p1
     }
     pub fn overlaps_with_p0_to(&self, target: Vec2) -> bool {
-        overlaps(self.vec(), target - self.p0)
+        overlaps(self.p0, self.p1, target)
     }
     pub fn overlaps_with_p1_to(&self, target: Vec2) -> bool {
-        overlaps(self.vec_rev(), target - self.p1)
+        overlaps(self.p1, self.p0, target)
+    }
+    /// Finds the point on the segment that is closest to `point`.
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        let vec = self.vec();
+        let length_squared = vec.dot(vec);
+        if length_squared == 0. {
+            return self.p0;
+        }
+        let t = (point - self.p0).dot(vec) / length_squared;
+        if t <= 0. {
+            self.p0
+        } else if t >= 1. {
+            self.p1
+        } else {
+            self.p0 + vec * t
+        }
     }
+    /// Finds the closest pair of points on `self` and `other`, in that order.
+    pub fn closest_points(&self, other: &Segment) -> (Vec2, Vec2) {
+        if let Some(intersection) = self.intersect(other) {
+            return (intersection, intersection);
+        }
+        // Without intersection, one of the closest points must be an endpoint
+        let candidates = [
+            (self.p0, other.closest_point(self.p0)),
+            (self.p1, other.closest_point(self.p1)),
+            (self.closest_point(other.p0), other.p0),
+            (self.closest_point(other.p1), other.p1),
+        ];
+        let mut closest = candidates[0];
+        for &candidate in &candidates[1..] {
+            if candidate.0.dist_squared(candidate.1) < closest.0.dist_squared(closest.1) {
+                closest = candidate;
+            }
+        }
+        closest
+    }
+}
+
+/// The length of the polyline through `path`.
+pub fn path_length(path: &[Vec2]) -> f64 {
+    path.windows(2).map(|points| points[0].dist(points[1])).sum()
 }
 
 #[cfg(test)]
@@ -384,6 +387,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn segment_closest_point() {
+        let segment = Segment::new_flat(0., 0., 10., 0.);
+        assert_eq!(segment.closest_point(Vec2::new(5., 5.)), Vec2::new(5., 0.));
+        assert_eq!(segment.closest_point(Vec2::new(-5., 5.)), Vec2::new(0., 0.));
+        assert_eq!(segment.closest_point(Vec2::new(15., -5.)), Vec2::new(10., 0.));
+        assert_eq!(
+            Segment::new_flat(1., 1., 1., 1.).closest_point(Vec2::new(5., 5.)),
+            Vec2::new(1., 1.)
+        );
+    }
+
+    #[test]
+    fn segment_closest_points() {
+        let segment = Segment::new_flat(0., 0., 10., 0.);
+        assert_eq!(
+            segment.closest_points(&Segment::new_flat(5., 5., 5., -5.)),
+            (Vec2::new(5., 0.), Vec2::new(5., 0.))
+        );
+        assert_eq!(
+            segment.closest_points(&Segment::new_flat(5., 2., 8., 7.)),
+            (Vec2::new(5., 0.), Vec2::new(5., 2.))
+        );
+        assert_eq!(
+            segment.closest_points(&Segment::new_flat(12., 3., 20., 3.)),
+            (Vec2::new(10., 0.), Vec2::new(12., 3.))
+        );
+    }
+
     #[test]
     fn points() {
         test_segment_intersection_switch_vert((((5., 5.), (5., 5.)), ((6., 1.), (6., 9.))), None);
@@ -394,4 +426,87 @@ mod tests {
         test_segment_intersection_switch_vert((((5., 5.), (5., 5.)), ((5., 5.), (5., 8.))), Some(Vec2::new(5., 5.)));
         test_segment_intersection_switch_vert((((5., 5.), (5., 5.)), ((5., 5.), (7., 6.))), Some(Vec2::new(5., 5.)));
     }
+
+    fn assert_intersection_near(seg0: Segment, seg1: Segment, expected: Vec2) {
+        for (a, b) in [(seg0, seg1), (seg1, seg0)] {
+            let intersection = a.intersect_segment(&b).expect("segments should intersect");
+            let tolerance = expected.magnitude() * 1e-12;
+            assert!(
+                (intersection - expected).magnitude() <= tolerance,
+                "{:?} is not close to {:?}",
+                intersection,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn degenerate_tiny_scale() {
+        assert_intersection_near(
+            Segment::new_flat(1e-20, 1e-20, 1e-19, 1e-19),
+            Segment::new_flat(0., 1e-19, 1e-19, 0.),
+            Vec2::new(5e-20, 5e-20),
+        );
+        test_segment_intersection_switch_vert((((1e-20, 1e-20), (4e-20, 4e-20)), ((0., 1e-19), (1e-19, 0.))), None);
+        test_segment_intersection_switch_vert((((0., 0.), (1e-100, 0.)), ((0., 1e-100), (1e-100, 1e-100))), None);
+    }
+
+    #[test]
+    fn degenerate_large_offset() {
+        let offset = 1e15;
+        assert_intersection_near(
+            Segment::new_flat(offset, offset, offset + 8., offset + 8.),
+            Segment::new_flat(offset, offset + 8., offset + 8., offset),
+            Vec2::new(offset + 4., offset + 4.),
+        );
+        // Parallel lines one unit apart
+        test_segment_intersection_switch_vert(
+            (
+                ((offset, offset), (offset + 4., offset + 2.)),
+                ((offset, offset + 1.), (offset + 4., offset + 3.)),
+            ),
+            None,
+        );
+        // Collinear overlap
+        test_segment_intersection(
+            Segment::new_flat(offset, offset, offset + 4., offset + 2.),
+            Segment::new_flat(offset + 2., offset + 1., offset + 8., offset + 4.),
+            Some(Vec2::new(offset + 2., offset + 1.)),
+        );
+    }
+
+    #[test]
+    fn degenerate_near_vertical() {
+        let x = 5. + f64::EPSILON * 4.;
+        assert_intersection_near(
+            Segment::new_flat(5., 0., x, 10.),
+            Segment::new_flat(0., 5., 10., 5.),
+            Vec2::new(5., 5.),
+        );
+        // Nearly parallel and only one ulp apart, but not touching
+        test_segment_intersection_switch_vert((((5., 0.), (5., 10.)), ((x, 0.), (x, 10.))), None);
+    }
+
+    #[test]
+    fn degenerate_t_junction() {
+        test_segment_intersection_switch_vert((((0., 0.), (10., 0.)), ((5., 0.), (5., 5.))), Some(Vec2::new(5., 0.)));
+        test_segment_intersection_switch_vert((((0., 0.), (10., 0.)), ((5., 1e-300), (5., 5.))), None);
+        // 0.1 and 0.3 are not representable, so whether the endpoint is on the segment is only
+        // known after an exact test, which both directions have to agree on
+        let segment = Segment::new_flat(0., 0., 0.3, 0.1);
+        let stem = Segment::new_flat(0.15, 0.05, 0.15, 1.);
+        assert_eq!(
+            segment.intersect_segment(&stem).is_some(),
+            stem.intersect_segment(&segment).is_some()
+        );
+    }
+
+    #[test]
+    fn degenerate_connective() {
+        let segment = Segment::new_flat(0., 0., 10., 0.);
+        assert!(!segment.connective_intersect(&Segment::new_flat(10., 0., 20., 0.)));
+        assert!(segment.connective_intersect(&Segment::new_flat(10., 0., 5., 0.)));
+        assert!(!segment.connective_intersect(&Segment::new_flat(0., 0., 1e-300, 1e-300)));
+        assert!(segment.connective_intersect(&Segment::new_flat(0., 0., 1e-300, 0.)));
+    }
 }
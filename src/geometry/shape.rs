@@ -1,8 +1,12 @@
 use wasm_bindgen::__rt::core::slice::Iter;
 
-use super::{IntersectSegment, Segment, Vec2};
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq)]
+use super::{Aabb, IntersectSegment, Segment, Vec2};
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Shape {
     pub vertices: Vec<Vec2>,
 }
@@ -41,22 +45,67 @@ impl Shape {
         }
     }
     pub fn winding_order(&self) -> ShapeWindingOrder {
-        let sum = self
-            .segments()
-            .into_iter()
-            .map(|segment| (segment.p1.x - segment.p0.x) * (segment.p0.y + segment.p1.y))
-            .sum::<f64>();
-        if sum < 0. {
+        let signed_area = self.signed_area();
+        if signed_area < 0. {
             ShapeWindingOrder::Clockwise
-        } else if sum > 0. {
+        } else if signed_area > 0. {
             ShapeWindingOrder::Counterclockwise
         } else {
             ShapeWindingOrder::Undetermined
         }
     }
+    /// The area enclosed by the shape, positive if its winding order is
+    /// `ShapeWindingOrder::Counterclockwise` and negative if it is `ShapeWindingOrder::Clockwise`.
+    pub fn signed_area(&self) -> f64 {
+        self.segments()
+            .into_iter()
+            .map(|segment| (segment.p1.x - segment.p0.x) * (segment.p0.y + segment.p1.y))
+            .sum::<f64>()
+            / 2.
+    }
+    pub fn area(&self) -> f64 {
+        self.signed_area().abs()
+    }
+    /// The center of mass of the enclosed area, `None` if the area is zero.
+    pub fn centroid(&self) -> Option<Vec2> {
+        let signed_area = self.signed_area();
+        if signed_area == 0. {
+            return None;
+        }
+        let sum = self.segments().into_iter().fold(Vec2::zero(), |sum, segment| {
+            sum + (segment.p0 + segment.p1) * segment.p1.cross(segment.p0)
+        });
+        Some(sum / (6. * signed_area))
+    }
+    /// The smallest axis-aligned bounding box containing all vertices, `None` if there are none.
+    pub fn bounds(&self) -> Option<Aabb> {
+        let first = *self.vertices.first()?;
+        let (min, max) = self.vertices.iter().fold((first, first), |(min, max), vertex| {
+            (
+                Vec2::new(min.x.min(vertex.x), min.y.min(vertex.y)),
+                Vec2::new(max.x.max(vertex.x), max.y.max(vertex.y)),
+            )
+        });
+        Some(Aabb::new(min, max))
+    }
     pub fn reverse(&mut self) {
         self.vertices.reverse();
     }
+    /// Tests whether `point` lies inside the shape using the even-odd rule. Points on the boundary
+    /// may be reported either way.
+    pub fn contains(&self, point: Vec2) -> bool {
+        let mut inside = false;
+        for segment in self.segments() {
+            if (segment.p0.y > point.y) != (segment.p1.y > point.y) {
+                let intersect_x = segment.p0.x
+                    + (point.y - segment.p0.y) / (segment.p1.y - segment.p0.y) * (segment.p1.x - segment.p0.x);
+                if point.x < intersect_x {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
 }
 
 pub struct Segments<'a> {
@@ -116,15 +165,17 @@ impl Iterator for SegmentsIter<'_> {
     }
 }
 
-impl IntersectSegment for Shape {
-    fn intersect_segment(&self, other: &Segment) -> Option<Vec2> {
-        let mut closest_intersection: Option<Vec2> = None;
+impl Shape {
+    /// Finds the intersection closest to `other.p0`, together with the index of the intersected
+    /// segment.
+    pub fn closest_intersection(&self, other: &Segment) -> Option<(usize, Vec2)> {
+        let mut closest_intersection: Option<(usize, Vec2)> = None;
         let mut closest_dist_squared: f64 = f64::INFINITY;
-        for segment in self.segments() {
+        for (segment_index, segment) in self.segments().into_iter().enumerate() {
             if let Some(intersection) = other.intersect_segment(&segment) {
                 let dist = other.p0.dist_squared(intersection);
                 if dist < closest_dist_squared {
-                    closest_intersection = Some(intersection);
+                    closest_intersection = Some((segment_index, intersection));
                     closest_dist_squared = dist;
                 }
             }
@@ -133,6 +184,12 @@ impl IntersectSegment for Shape {
     }
 }
 
+impl IntersectSegment for Shape {
+    fn intersect_segment(&self, other: &Segment) -> Option<Vec2> {
+        self.closest_intersection(other).map(|(_, intersection)| intersection)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -174,6 +231,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn shape_contains() {
+        let shape = Shape::new(vec![
+            Vec2::new(0., 0.),
+            Vec2::new(10., 0.),
+            Vec2::new(10., 10.),
+            Vec2::new(5., 5.),
+            Vec2::new(0., 10.),
+        ]);
+        assert!(shape.contains(Vec2::new(5., 2.)));
+        assert!(shape.contains(Vec2::new(1., 8.)));
+        assert!(!shape.contains(Vec2::new(5., 8.)));
+        assert!(!shape.contains(Vec2::new(-1., 5.)));
+        assert!(!shape.contains(Vec2::new(11., 5.)));
+        assert!(!Shape::new_empty().contains(Vec2::new(0., 0.)));
+    }
+
+    #[test]
+    fn shape_area_centroid_bounds() {
+        let mut shape = Shape::new(vec![
+            Vec2::new(0., 0.),
+            Vec2::new(0., 10.),
+            Vec2::new(20., 10.),
+            Vec2::new(20., 0.),
+        ]);
+        assert_eq!(shape.signed_area(), 200.);
+        assert_eq!(shape.centroid(), Some(Vec2::new(10., 5.)));
+        shape.reverse();
+        assert_eq!(shape.signed_area(), -200.);
+        assert_eq!(shape.area(), 200.);
+        assert_eq!(shape.centroid(), Some(Vec2::new(10., 5.)));
+        assert_eq!(shape.bounds(), Some(Aabb::new(Vec2::new(0., 0.), Vec2::new(20., 10.))));
+        let line = Shape::new(vec![Vec2::new(0., 0.), Vec2::new(1., 1.)]);
+        assert_eq!(line.centroid(), None);
+        assert_eq!(Shape::new_empty().bounds(), None);
+    }
+
     #[test]
     fn shape_intersect_segment() {
         assert_eq!(
@@ -0,0 +1,200 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use super::{Segment, Shape, Vec2};
+
+impl Shape {
+    /// Douglas–Peucker simplification: removes vertices so that every removed vertex is at most
+    /// `tolerance` away from the simplified outline. Shapes with more than three vertices are never
+    /// simplified to fewer than three.
+    pub fn simplify_douglas_peucker(&self, tolerance: f64) -> Shape {
+        let vertices = &self.vertices;
+        let vertices_count = vertices.len();
+        if vertices_count <= 3 {
+            return self.clone();
+        }
+        let vertex = |index: usize| vertices[index % vertices_count];
+        // Split the closed outline into two chains, between the first vertex and the vertex
+        // farthest away from it
+        let farthest_index = (1..vertices_count)
+            .max_by(|&index0, &index1| {
+                let dist0 = vertices[0].dist_squared(vertices[index0]);
+                let dist1 = vertices[0].dist_squared(vertices[index1]);
+                dist0.partial_cmp(&dist1).unwrap()
+            })
+            .unwrap();
+        let mut keep = vec![false; vertices_count];
+        keep[0] = true;
+        keep[farthest_index] = true;
+        let mut chains = vec![(0, farthest_index), (farthest_index, vertices_count)];
+        while let Some((start, end)) = chains.pop() {
+            let segment = Segment::new(vertex(start), vertex(end));
+            let farthest = (start + 1..end)
+                .map(|index| (index, vertex(index).dist(segment.closest_point(vertex(index)))))
+                .max_by(|(_, dist0), (_, dist1)| dist0.partial_cmp(dist1).unwrap());
+            if let Some((index, dist)) = farthest {
+                if dist > tolerance {
+                    keep[index] = true;
+                    chains.push((start, index));
+                    chains.push((index, end));
+                }
+            }
+        }
+        let mut simplified: Vec<Vec2> = (0..vertices_count)
+            .filter(|&index| keep[index])
+            .map(|index| vertices[index])
+            .collect();
+        if simplified.len() < 3 {
+            // Everything is within the tolerance of the line between the two remaining vertices,
+            // keep the vertex farthest from it to still enclose some area
+            let segment = Segment::new(vertices[0], vertices[farthest_index]);
+            let index = (1..vertices_count)
+                .filter(|&index| index != farthest_index)
+                .max_by(|&index0, &index1| {
+                    let dist0 = vertices[index0].dist_squared(segment.closest_point(vertices[index0]));
+                    let dist1 = vertices[index1].dist_squared(segment.closest_point(vertices[index1]));
+                    dist0.partial_cmp(&dist1).unwrap()
+                })
+                .unwrap();
+            simplified.insert(if index < farthest_index { 1 } else { 2 }, vertices[index]);
+        }
+        Shape::new(simplified)
+    }
+    /// Visvalingam–Whyatt simplification: repeatedly removes the vertex that forms the triangle
+    /// with the smallest area with its neighbours, as long as that area is less than `min_area`.
+    /// Shapes are never simplified to fewer than three vertices.
+    pub fn simplify_visvalingam(&self, min_area: f64) -> Shape {
+        let vertices = &self.vertices;
+        let vertices_count = vertices.len();
+        if vertices_count <= 3 {
+            return self.clone();
+        }
+        // A doubly linked list of the remaining vertices
+        let mut prev: Vec<usize> = (0..vertices_count)
+            .map(|index| (index + vertices_count - 1) % vertices_count)
+            .collect();
+        let mut next: Vec<usize> = (0..vertices_count).map(|index| (index + 1) % vertices_count).collect();
+        let mut removed = vec![false; vertices_count];
+        let area = |prev_index: usize, index: usize, next_index: usize| {
+            ((vertices[prev_index] - vertices[index]).cross(vertices[next_index] - vertices[index]) / 2.).abs()
+        };
+        // Areas are pushed again whenever a neighbour is removed, outdated entries are skipped by
+        // comparing them with the current neighbours
+        let mut queue: BinaryHeap<TriangleArea> = (0..vertices_count)
+            .map(|index| TriangleArea {
+                area: area(prev[index], index, next[index]),
+                index,
+                prev: prev[index],
+                next: next[index],
+            })
+            .collect();
+        let mut remaining = vertices_count;
+        while remaining > 3 {
+            let triangle = match queue.pop() {
+                Some(triangle) => triangle,
+                None => break,
+            };
+            if removed[triangle.index] || prev[triangle.index] != triangle.prev || next[triangle.index] != triangle.next
+            {
+                continue;
+            }
+            if triangle.area >= min_area {
+                break;
+            }
+            removed[triangle.index] = true;
+            remaining -= 1;
+            next[triangle.prev] = triangle.next;
+            prev[triangle.next] = triangle.prev;
+            for &index in &[triangle.prev, triangle.next] {
+                queue.push(TriangleArea {
+                    area: area(prev[index], index, next[index]),
+                    index,
+                    prev: prev[index],
+                    next: next[index],
+                });
+            }
+        }
+        Shape::new(
+            (0..vertices_count)
+                .filter(|&index| !removed[index])
+                .map(|index| vertices[index])
+                .collect(),
+        )
+    }
+}
+
+struct TriangleArea {
+    area: f64,
+    index: usize,
+    prev: usize,
+    next: usize,
+}
+
+impl PartialEq for TriangleArea {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TriangleArea {}
+
+impl Ord for TriangleArea {
+    /// Reversed, so that the smallest area is on top of the `BinaryHeap`
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .area
+            .partial_cmp(&self.area)
+            .unwrap()
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for TriangleArea {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 10x10 square with slightly noisy edges.
+    fn noisy_square() -> Shape {
+        Shape::new(vec![
+            Vec2::new(0., 0.),
+            Vec2::new(3., 0.1),
+            Vec2::new(6., -0.1),
+            Vec2::new(10., 0.),
+            Vec2::new(10.1, 5.),
+            Vec2::new(10., 10.),
+            Vec2::new(5., 10.05),
+            Vec2::new(0., 10.),
+            Vec2::new(-0.1, 4.),
+        ])
+    }
+
+    fn square() -> Shape {
+        Shape::new(vec![
+            Vec2::new(0., 0.),
+            Vec2::new(10., 0.),
+            Vec2::new(10., 10.),
+            Vec2::new(0., 10.),
+        ])
+    }
+
+    #[test]
+    fn douglas_peucker() {
+        assert_eq!(noisy_square().simplify_douglas_peucker(0.5), square());
+        assert_eq!(noisy_square().simplify_douglas_peucker(0.01), noisy_square());
+        // Never collapses into a line
+        assert_eq!(noisy_square().simplify_douglas_peucker(100.).vertices.len(), 3);
+    }
+
+    #[test]
+    fn visvalingam() {
+        assert_eq!(noisy_square().simplify_visvalingam(1.), square());
+        assert_eq!(noisy_square().simplify_visvalingam(0.01), noisy_square());
+        assert_eq!(noisy_square().simplify_visvalingam(1000.).vertices.len(), 3);
+    }
+}
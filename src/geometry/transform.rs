@@ -0,0 +1,154 @@
+use std::ops::Mul;
+
+use super::{Segment, Shape, Vec2};
+
+/// An affine transform, mapping `point` to `x_axis * point.x + y_axis * point.y + translation`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform2 {
+    pub x_axis: Vec2,
+    pub y_axis: Vec2,
+    pub translation: Vec2,
+}
+
+impl Transform2 {
+    pub fn identity() -> Self {
+        Transform2 {
+            x_axis: Vec2::new(1., 0.),
+            y_axis: Vec2::new(0., 1.),
+            translation: Vec2::zero(),
+        }
+    }
+    pub fn translation(offset: Vec2) -> Self {
+        Transform2 {
+            translation: offset,
+            ..Transform2::identity()
+        }
+    }
+    /// Rotates around the origin, from the positive x axis towards the positive y axis.
+    pub fn rotation(radians: f64) -> Self {
+        Transform2 {
+            x_axis: Vec2::new(1., 0.).rotate(radians),
+            y_axis: Vec2::new(0., 1.).rotate(radians),
+            translation: Vec2::zero(),
+        }
+    }
+    pub fn scale(factor: f64) -> Self {
+        Transform2::scale_xy(factor, factor)
+    }
+    pub fn scale_xy(factor_x: f64, factor_y: f64) -> Self {
+        Transform2 {
+            x_axis: Vec2::new(factor_x, 0.),
+            y_axis: Vec2::new(0., factor_y),
+            translation: Vec2::zero(),
+        }
+    }
+    /// Scales, then rotates, then translates, which is the usual way to place a prefab.
+    pub fn from_scale_rotation_translation(scale: Vec2, radians: f64, translation: Vec2) -> Self {
+        Transform2::scale_xy(scale.x, scale.y)
+            .then(Transform2::rotation(radians))
+            .then(Transform2::translation(translation))
+    }
+    /// The transform that applies `self` first and `other` afterwards.
+    pub fn then(self, other: Transform2) -> Self {
+        Transform2 {
+            x_axis: other.apply_to_vector(self.x_axis),
+            y_axis: other.apply_to_vector(self.y_axis),
+            translation: other.apply(self.translation),
+        }
+    }
+    /// Positive if the transform preserves the winding order of shapes, negative if it mirrors
+    /// them, and zero if it collapses them.
+    pub fn determinant(&self) -> f64 {
+        self.x_axis.cross(self.y_axis)
+    }
+    pub fn inverse(&self) -> Option<Self> {
+        let determinant = self.determinant();
+        if determinant == 0. {
+            return None;
+        }
+        let x_axis = Vec2::new(self.y_axis.y, -self.x_axis.y) / determinant;
+        let y_axis = Vec2::new(-self.y_axis.x, self.x_axis.x) / determinant;
+        Some(Transform2 {
+            x_axis,
+            y_axis,
+            translation: Vec2::zero() - (x_axis * self.translation.x + y_axis * self.translation.y),
+        })
+    }
+    pub fn apply(&self, point: Vec2) -> Vec2 {
+        self.apply_to_vector(point) + self.translation
+    }
+    /// Transforms a direction or offset, i.e. without the translation.
+    pub fn apply_to_vector(&self, vector: Vec2) -> Vec2 {
+        self.x_axis * vector.x + self.y_axis * vector.y
+    }
+}
+
+impl Default for Transform2 {
+    fn default() -> Self {
+        Transform2::identity()
+    }
+}
+
+impl Mul<Vec2> for Transform2 {
+    type Output = Vec2;
+
+    fn mul(self, rhs: Vec2) -> Self::Output {
+        self.apply(rhs)
+    }
+}
+
+impl Segment {
+    pub fn transform(&self, transform: &Transform2) -> Segment {
+        Segment::new(transform.apply(self.p0), transform.apply(self.p1))
+    }
+}
+
+impl Shape {
+    /// The vertices keep their order, so a mirroring transform reverses the winding order.
+    pub fn transform(&self, transform: &Transform2) -> Shape {
+        Shape::new(self.vertices.iter().map(|&vertex| transform.apply(vertex)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn assert_near(actual: Vec2, expected: Vec2) {
+        assert!(
+            actual.dist(expected) < 1e-9,
+            "{:?} is not close to {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn transform_point() {
+        let transform = Transform2::from_scale_rotation_translation(Vec2::new(2., 3.), PI / 2., Vec2::new(10., 0.));
+        assert_near(transform.apply(Vec2::new(1., 0.)), Vec2::new(10., 2.));
+        assert_near(transform * Vec2::new(0., 1.), Vec2::new(7., 0.));
+        assert_near(transform.apply_to_vector(Vec2::new(1., 1.)), Vec2::new(-3., 2.));
+        assert_near(
+            transform.inverse().unwrap().apply(transform.apply(Vec2::new(4., -5.))),
+            Vec2::new(4., -5.),
+        );
+        assert_eq!(Transform2::scale(0.).inverse(), None);
+        assert!(Transform2::scale_xy(1., -1.).determinant() < 0.);
+    }
+
+    #[test]
+    fn transform_shape() {
+        let shape = Shape::new(vec![Vec2::new(0., 0.), Vec2::new(1., 0.), Vec2::new(0., 1.)]);
+        let transformed = shape.transform(&Transform2::translation(Vec2::new(5., 5.)).then(Transform2::scale(2.)));
+        assert_eq!(
+            transformed,
+            Shape::new(vec![Vec2::new(10., 10.), Vec2::new(12., 10.), Vec2::new(10., 12.)])
+        );
+        assert_eq!(
+            Segment::new_flat(0., 0., 1., 0.).transform(&Transform2::translation(Vec2::new(0., 1.))),
+            Segment::new_flat(0., 1., 1., 1.)
+        );
+    }
+}
@@ -1,15 +1,27 @@
-use std::ops::{Add, Div, Mul};
+use std::ops::{Add, Div, Mul, Neg};
 
 use crate::geometry::Direction;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::__rt::core::ops::Sub;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Vec2 {
     pub x: f64,
     pub y: f64,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// Exact identity of a point, for looking points up in a map, see `point_key`.
+pub(crate) type PointKey = (u64, u64);
+
+/// The key of a point, which is the same for all points that compare equal.
+pub(crate) fn point_key(point: Vec2) -> PointKey {
+    // Adding zero turns -0 into 0
+    ((point.x + 0.).to_bits(), (point.y + 0.).to_bits())
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Sign {
     Negative,
     Zero,
@@ -18,9 +30,9 @@ pub enum Sign {
 
 impl Sign {
     pub fn of(value: f64) -> Self {
-        if value <= -f64::EPSILON {
+        if value < 0. {
             Sign::Negative
-        } else if value >= f64::EPSILON {
+        } else if value > 0. {
             Sign::Positive
         } else {
             Sign::Zero
@@ -28,6 +40,18 @@ impl Sign {
     }
 }
 
+impl Neg for Sign {
+    type Output = Sign;
+
+    fn neg(self) -> Self::Output {
+        match self {
+            Sign::Negative => Sign::Positive,
+            Sign::Zero => Sign::Zero,
+            Sign::Positive => Sign::Negative,
+        }
+    }
+}
+
 impl Vec2 {
     pub fn new(x: f64, y: f64) -> Self {
         Vec2 { x, y }
@@ -64,7 +88,7 @@ impl Vec2 {
         Sign::of(self.y)
     }
     pub fn is_zero(self) -> bool {
-        self.x <= f64::EPSILON && self.y <= f64::EPSILON
+        self.x == 0. && self.y == 0.
     }
 }
 
@@ -116,6 +140,17 @@ impl Vec2 {
     pub fn cross(self, rhs: Self) -> f64 {
         self.x * rhs.y - self.y * rhs.x
     }
+    pub fn dot(self, rhs: Self) -> f64 {
+        self.x * rhs.x + self.y * rhs.y
+    }
+    /// Rotates around the origin, from the positive x axis towards the positive y axis.
+    pub fn rotate(self, radians: f64) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        Vec2 {
+            x: self.x * cos - self.y * sin,
+            y: self.x * sin + self.y * cos,
+        }
+    }
     pub fn atan2(self) -> f64 {
         self.y.atan2(self.x)
     }
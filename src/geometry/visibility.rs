@@ -0,0 +1,148 @@
+use std::f64::consts::PI;
+
+use super::{Aabb, Segment, Shape, Vec2};
+
+/// Rays are cast this far (in radians) to both sides of every vertex to see past it.
+const VERTEX_ANGLE_OFFSET: f64 = 1e-9;
+
+fn bound_angle(angle: f64) -> f64 {
+    if angle > PI {
+        angle - 2. * PI
+    } else if angle <= -PI {
+        angle + 2. * PI
+    } else {
+        angle
+    }
+}
+
+fn cast_ray(viewpoint: Vec2, angle: f64, length: f64, segments: &[Segment]) -> Vec2 {
+    let ray = Segment::new(viewpoint, viewpoint + Vec2::new(angle.cos(), angle.sin()) * length);
+    let mut closest_hit = ray.p1;
+    let mut closest_dist_squared = f64::INFINITY;
+    for segment in segments {
+        if let Some(hit) = ray.intersect(segment) {
+            let dist_squared = viewpoint.dist_squared(hit);
+            if dist_squared < closest_dist_squared {
+                closest_hit = hit;
+                closest_dist_squared = dist_squared;
+            }
+        }
+    }
+    closest_hit
+}
+
+/// Casts rays towards every vertex (and slightly to both sides of it), sorted by their angle
+/// relative to `base_angle`. Only rays within `max_angle` of `base_angle` are kept.
+fn cast_rays(viewpoint: Vec2, obstacles: &[&Shape], bounds: Aabb, base_angle: f64, max_angle: f64) -> Vec<Vec2> {
+    let mut segments: Vec<Segment> = obstacles
+        .iter()
+        .flat_map(|obstacle| obstacle.segments().into_iter())
+        .collect();
+    segments.extend(bounds.segments().iter());
+    let length = (bounds.width() + bounds.height()) * 2.;
+
+    let mut angles = vec![];
+    let bounds_vertices = bounds.vertices();
+    let vertices = obstacles
+        .iter()
+        .flat_map(|obstacle| obstacle.vertices.iter())
+        .chain(bounds_vertices.iter())
+        .copied();
+    for vertex in vertices {
+        if vertex == viewpoint {
+            continue;
+        }
+        let angle = bound_angle((vertex - viewpoint).atan2() - base_angle);
+        for &offset in &[-VERTEX_ANGLE_OFFSET, 0., VERTEX_ANGLE_OFFSET] {
+            angles.push(angle + offset);
+        }
+    }
+    if max_angle < PI {
+        angles.push(-max_angle);
+        angles.push(max_angle);
+    }
+    angles.retain(|angle| angle.abs() <= max_angle);
+    angles.sort_by(|angle0, angle1| angle0.partial_cmp(angle1).unwrap());
+    angles.dedup();
+    angles
+        .into_iter()
+        .map(|angle| cast_ray(viewpoint, base_angle + angle, length, &segments))
+        .collect()
+}
+
+fn is_visible(viewpoint: Vec2, obstacles: &[&Shape], bounds: Aabb) -> bool {
+    bounds.contains(viewpoint) && !obstacles.iter().any(|obstacle| obstacle.contains(viewpoint))
+}
+
+/// Computes the polygon of all points within `bounds` that are visible from `viewpoint`. The
+/// polygon is empty if `viewpoint` is inside an obstacle or outside of `bounds`.
+pub fn visibility_polygon(viewpoint: Vec2, obstacles: &[&Shape], bounds: Aabb) -> Shape {
+    if !is_visible(viewpoint, obstacles, bounds) {
+        return Shape::new_empty();
+    }
+    Shape::new(cast_rays(viewpoint, obstacles, bounds, 0., PI))
+}
+
+/// Same as `visibility_polygon`, but only includes points within `half_angle` (in radians) of
+/// `direction`. Unless the cone covers all directions, `viewpoint` is the first vertex of the
+/// polygon.
+pub fn visibility_cone(viewpoint: Vec2, obstacles: &[&Shape], bounds: Aabb, direction: Vec2, half_angle: f64) -> Shape {
+    debug_assert!(half_angle > 0.);
+    if !is_visible(viewpoint, obstacles, bounds) {
+        return Shape::new_empty();
+    }
+    if half_angle >= PI {
+        return visibility_polygon(viewpoint, obstacles, bounds);
+    }
+    let mut vertices = vec![viewpoint];
+    vertices.extend(cast_rays(viewpoint, obstacles, bounds, direction.atan2(), half_angle));
+    Shape::new(vertices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f64, y: f64, size: f64) -> Shape {
+        Shape::new(vec![
+            Vec2::new(x, y),
+            Vec2::new(x + size, y),
+            Vec2::new(x + size, y + size),
+            Vec2::new(x, y + size),
+        ])
+    }
+
+    #[test]
+    fn visibility_polygon_behind_obstacle() {
+        let obstacle = square(40., 40., 20.);
+        let bounds = Aabb::new(Vec2::new(0., 0.), Vec2::new(100., 100.));
+        let polygon = visibility_polygon(Vec2::new(10., 50.), &[&obstacle], bounds);
+        assert!(polygon.contains(Vec2::new(30., 50.)));
+        assert!(polygon.contains(Vec2::new(90., 10.)));
+        assert!(polygon.contains(Vec2::new(50., 30.)));
+        assert!(!polygon.contains(Vec2::new(50., 50.)));
+        assert!(!polygon.contains(Vec2::new(90., 50.)));
+        assert!(!polygon.contains(Vec2::new(110., 50.)));
+    }
+
+    #[test]
+    fn visibility_polygon_inside_obstacle() {
+        let obstacle = square(40., 40., 20.);
+        let bounds = Aabb::new(Vec2::new(0., 0.), Vec2::new(100., 100.));
+        assert!(visibility_polygon(Vec2::new(50., 50.), &[&obstacle], bounds).is_empty());
+        assert!(visibility_polygon(Vec2::new(150., 50.), &[&obstacle], bounds).is_empty());
+    }
+
+    #[test]
+    fn visibility_cone_behind_obstacle() {
+        let obstacle = square(40., 40., 20.);
+        let bounds = Aabb::new(Vec2::new(0., 0.), Vec2::new(100., 100.));
+        let cone = visibility_cone(Vec2::new(10., 50.), &[&obstacle], bounds, Vec2::new(1., 0.), PI / 4.);
+        assert_eq!(cone.vertices[0], Vec2::new(10., 50.));
+        assert!(cone.contains(Vec2::new(30., 50.)));
+        assert!(cone.contains(Vec2::new(50., 30.)));
+        assert!(!cone.contains(Vec2::new(50., 50.)));
+        assert!(!cone.contains(Vec2::new(20., 90.)));
+        assert!(!cone.contains(Vec2::new(5., 50.)));
+    }
+}
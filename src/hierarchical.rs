@@ -0,0 +1,469 @@
+//! Hierarchical path finding for worlds too large for a single visibility graph. The world is
+//! partitioned into a grid of square clusters, each with its own `Navigation` over the obstacles
+//! near it. Entrances are placed on the free parts of the borders between clusters and the
+//! distances between the entrances of every cluster are precomputed, which gives a small abstract
+//! graph. A path is searched over the abstract graph first and then refined within every cluster
+//! it passes through.
+//!
+//! The paths are not always the shortest, since they have to pass the cluster borders at the
+//! entrances.
+
+use std::collections::HashMap;
+
+use noisy_float::prelude::*;
+use noisy_float::types::N64;
+
+use crate::a_star::{a_star, AStarInput};
+use crate::geometry::{orient2d, path_length, Aabb, Segment, Shape, Sign, Vec2};
+use crate::navigation::{Navigation, NavigationObstacle, PathFinder};
+
+struct Cluster {
+    /// The cluster enlarged by a margin. The navigation of the cluster contains every obstacle
+    /// that overlaps the region, so paths that stay within it cannot cross any other obstacle.
+    region: Aabb,
+    navigation: Navigation,
+    entrances: Vec<usize>,
+}
+
+/// Places entrances on the parts of `border` that are not covered by `shapes`. The border is split
+/// where the edges of the shapes cross or touch it, and long free parts get multiple entrances at
+/// most `spacing` apart.
+pub(crate) fn border_entrances(border: Segment, shapes: &[&Shape], spacing: f64) -> Vec<Vec2> {
+    let length = border.vec().magnitude();
+    let t_at = |point: Vec2| (point - border.p0).dot(border.vec()) / (length * length);
+    let mut splits = vec![0., 1.];
+    for shape in shapes {
+        for segment in shape.segments() {
+            if orient2d(border.p0, border.p1, segment.p0) == Sign::Zero
+                && orient2d(border.p0, border.p1, segment.p1) == Sign::Zero
+            {
+                // Lying on the border
+                splits.push(t_at(segment.p0).clamp(0., 1.));
+                splits.push(t_at(segment.p1).clamp(0., 1.));
+            } else if let Some(point) = border.intersect(&segment) {
+                splits.push(t_at(point));
+            }
+        }
+    }
+    splits.sort_by(|t0, t1| t0.partial_cmp(t1).unwrap());
+    let point_at = |t: f64| border.p0 + border.vec() * t;
+    let mut entrances = vec![];
+    for interval in splits.windows(2) {
+        let interval_length = (interval[1] - interval[0]) * length;
+        if interval_length < 1e-9 {
+            continue;
+        }
+        let middle = point_at((interval[0] + interval[1]) / 2.);
+        let is_free = shapes.iter().all(|shape| {
+            !shape.contains(middle)
+                && shape
+                    .segments()
+                    .into_iter()
+                    .all(|segment| segment.closest_point(middle).dist(middle) > 1e-9)
+        });
+        if !is_free {
+            continue;
+        }
+        let count = (interval_length / spacing).ceil() as usize;
+        entrances.extend(
+            (0..count)
+                .map(|part| point_at(interval[0] + (interval[1] - interval[0]) * (part as f64 + 0.5) / count as f64)),
+        );
+    }
+    entrances
+}
+
+/// The regions an `AbstractGraph` is refined in, e.g. clusters or chunks.
+pub(crate) trait AbstractRegions {
+    type Region: Copy;
+    fn entrances(&self, region: Self::Region) -> &[usize];
+    /// The path between two points of the region, which is `None` if it has to leave the region.
+    fn local_path(&self, region: Self::Region, start: Vec2, end: Vec2) -> Option<Vec<Vec2>>;
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AbstractEdge<R> {
+    cost: f64,
+    /// The region both ends of the edge belong to, which refines it.
+    region: R,
+}
+
+/// A graph over entrances between regions, whose edges are paths within a single region.
+pub(crate) struct AbstractGraph<R> {
+    entrances: Vec<Vec2>,
+    neighbors: Vec<Vec<usize>>,
+    edges: HashMap<(usize, usize), AbstractEdge<R>>,
+}
+
+impl<R: Copy + PartialEq> AbstractGraph<R> {
+    pub(crate) fn new() -> Self {
+        AbstractGraph {
+            entrances: vec![],
+            neighbors: vec![],
+            edges: HashMap::new(),
+        }
+    }
+    pub(crate) fn len(&self) -> usize {
+        self.entrances.len()
+    }
+    pub(crate) fn add_entrance(&mut self, position: Vec2) -> usize {
+        self.entrances.push(position);
+        self.neighbors.push(vec![]);
+        self.entrances.len() - 1
+    }
+    /// Connects two entrances by a path of length `cost` within `region`. Two regions can connect
+    /// the same entrances, in which case the shorter connection is kept.
+    pub(crate) fn connect(&mut self, entrance0: usize, entrance1: usize, cost: f64, region: R) {
+        match self.edges.get(&(entrance0, entrance1)) {
+            Some(edge) if edge.cost <= cost => return,
+            Some(_) => {}
+            None => {
+                self.neighbors[entrance0].push(entrance1);
+                self.neighbors[entrance1].push(entrance0);
+            }
+        }
+        let edge = AbstractEdge { cost, region };
+        self.edges.insert((entrance0, entrance1), edge);
+        self.edges.insert((entrance1, entrance0), edge);
+    }
+    /// Connects every pair of entrances of the region that has a path within it.
+    pub(crate) fn connect_region<Regions>(&mut self, regions: &Regions, region: R)
+    where
+        Regions: AbstractRegions<Region = R>,
+    {
+        let entrances = regions.entrances(region);
+        for (index, &entrance0) in entrances.iter().enumerate() {
+            for &entrance1 in &entrances[index + 1..] {
+                if let Some(path) = regions.local_path(region, self.entrances[entrance0], self.entrances[entrance1]) {
+                    self.connect(entrance0, entrance1, path_length(&path), region);
+                }
+            }
+        }
+    }
+    /// Searches the abstract graph between two points in the given regions, and then refines the
+    /// path within every region it passes through.
+    pub(crate) fn find_path<Regions>(&self, regions: &Regions, start: (Vec2, R), end: (Vec2, R)) -> Option<Vec<Vec2>>
+    where
+        Regions: AbstractRegions<Region = R>,
+    {
+        let start_node = self.entrances.len();
+        let end_node = start_node + 1;
+
+        // Connect the start and end point to the entrances of their regions
+        let mut query_neighbors: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut query_edges = HashMap::new();
+        let mut connect = |from: usize, to: usize, cost: f64, region: R| {
+            for (node, other) in [(from, to), (to, from)] {
+                query_neighbors
+                    .entry(node)
+                    .or_insert_with(|| self.neighbors.get(node).cloned().unwrap_or_default())
+                    .push(other);
+                query_edges.insert((node, other), AbstractEdge { cost, region });
+            }
+        };
+        for (node, (point, region)) in [(start_node, start), (end_node, end)] {
+            for &entrance in regions.entrances(region) {
+                if let Some(path) = regions.local_path(region, point, self.entrances[entrance]) {
+                    connect(node, entrance, path_length(&path), region);
+                }
+            }
+        }
+        if start.1 == end.1 {
+            if let Some(path) = regions.local_path(start.1, start.0, end.0) {
+                connect(start_node, end_node, path_length(&path), start.1);
+            }
+        }
+
+        let a_star_input = AbstractAStarInput {
+            graph: self,
+            start: start.0,
+            end: end.0,
+            query_neighbors,
+            query_edges,
+        };
+        let abstract_path = a_star(&a_star_input)?;
+
+        let mut path = vec![start.0];
+        for nodes in abstract_path.windows(2) {
+            let edge = a_star_input.edge(nodes[0], nodes[1]);
+            let from = a_star_input.get_position(nodes[0]);
+            let to = a_star_input.get_position(nodes[1]);
+            let local_path = regions.local_path(edge.region, from, to)?;
+            path.extend(local_path.into_iter().skip(1));
+        }
+        Some(path)
+    }
+}
+
+/// The abstract graph, with the start and end point as two extra nodes after the entrances.
+struct AbstractAStarInput<'a, R> {
+    graph: &'a AbstractGraph<R>,
+    start: Vec2,
+    end: Vec2,
+    /// Neighbors of the nodes that are connected to the start or end point.
+    query_neighbors: HashMap<usize, Vec<usize>>,
+    query_edges: HashMap<(usize, usize), AbstractEdge<R>>,
+}
+
+impl<R: Copy> AbstractAStarInput<'_, R> {
+    fn get_position(&self, node: usize) -> Vec2 {
+        if node == self.start() {
+            self.start
+        } else if node == self.end() {
+            self.end
+        } else {
+            self.graph.entrances[node]
+        }
+    }
+    fn edge(&self, from: usize, to: usize) -> AbstractEdge<R> {
+        self.query_edges
+            .get(&(from, to))
+            .or_else(|| self.graph.edges.get(&(from, to)))
+            .copied()
+            .unwrap()
+    }
+}
+
+impl<R: Copy> AStarInput for AbstractAStarInput<'_, R> {
+    fn neighbors(&self, node: usize) -> &[usize] {
+        match self.query_neighbors.get(&node) {
+            Some(neighbors) => neighbors,
+            None => self.graph.neighbors.get(node).map_or(&[], Vec::as_slice),
+        }
+    }
+    fn distance(&self, from: usize, to: usize) -> N64 {
+        n64(self.edge(from, to).cost)
+    }
+    fn heuristic(&self, node: usize) -> N64 {
+        n64(self.get_position(node).dist(self.end))
+    }
+    fn len(&self) -> usize {
+        self.graph.entrances.len() + 2
+    }
+    fn start(&self) -> usize {
+        self.graph.entrances.len()
+    }
+    fn end(&self) -> usize {
+        self.graph.entrances.len() + 1
+    }
+}
+
+pub struct HierarchicalNavigation {
+    bounds: Aabb,
+    cluster_size: f64,
+    columns: usize,
+    rows: usize,
+    clusters: Vec<Cluster>,
+    graph: AbstractGraph<usize>,
+}
+
+impl HierarchicalNavigation {
+    /// Partitions `bounds` into clusters of `cluster_size` by `cluster_size`. Paths can only be
+    /// found between points within `bounds`.
+    pub fn new(obstacles: Vec<NavigationObstacle>, bounds: Aabb, cluster_size: f64) -> Self {
+        let columns = ((bounds.width() / cluster_size).ceil() as usize).max(1);
+        let rows = ((bounds.height() / cluster_size).ceil() as usize).max(1);
+        let margin = cluster_size / 2.;
+
+        let cluster_bounds = |column: usize, row: usize| {
+            let min = bounds.min + Vec2::new(column as f64, row as f64) * cluster_size;
+            Aabb::new(min, min + Vec2::new(cluster_size, cluster_size))
+        };
+        let mut cluster_obstacles: Vec<Vec<usize>> = vec![vec![]; columns * rows];
+        for (obstacle_index, obstacle) in obstacles.iter().enumerate() {
+            let obstacle_bounds = match obstacle.shape().bounds() {
+                Some(obstacle_bounds) => obstacle_bounds.expand(margin),
+                None => continue,
+            };
+            let cell = |value: f64, min: f64, count: usize| {
+                (((value - min) / cluster_size).floor().max(0.) as usize).min(count - 1)
+            };
+            for row in cell(obstacle_bounds.min.y, bounds.min.y, rows)..=cell(obstacle_bounds.max.y, bounds.min.y, rows)
+            {
+                for column in cell(obstacle_bounds.min.x, bounds.min.x, columns)
+                    ..=cell(obstacle_bounds.max.x, bounds.min.x, columns)
+                {
+                    cluster_obstacles[row * columns + column].push(obstacle_index);
+                }
+            }
+        }
+        let mut clusters: Vec<Cluster> = (0..columns * rows)
+            .map(|cluster_index| Cluster {
+                region: cluster_bounds(cluster_index % columns, cluster_index / columns).expand(margin),
+                navigation: Navigation::new(
+                    cluster_obstacles[cluster_index]
+                        .iter()
+                        .map(|&obstacle_index| obstacles[obstacle_index].clone())
+                        .collect(),
+                ),
+                entrances: vec![],
+            })
+            .collect();
+
+        let mut graph = AbstractGraph::new();
+        for row in 0..rows {
+            for column in 0..columns {
+                let cluster_index = row * columns + column;
+                let cluster_bounds = cluster_bounds(column, row);
+                // The right and the top border, shared with the next cluster in that direction
+                let mut borders = vec![];
+                if column + 1 < columns {
+                    borders.push((
+                        Segment::new(
+                            Vec2::new(cluster_bounds.max.x, cluster_bounds.min.y),
+                            cluster_bounds.max,
+                        ),
+                        cluster_index + 1,
+                    ));
+                }
+                if row + 1 < rows {
+                    borders.push((
+                        Segment::new(
+                            Vec2::new(cluster_bounds.min.x, cluster_bounds.max.y),
+                            cluster_bounds.max,
+                        ),
+                        cluster_index + columns,
+                    ));
+                }
+                for (border, other_cluster_index) in borders {
+                    let shapes: Vec<&Shape> = cluster_obstacles[cluster_index]
+                        .iter()
+                        .map(|&obstacle_index| obstacles[obstacle_index].shape())
+                        .collect();
+                    for position in border_entrances(border, &shapes, cluster_size / 4.) {
+                        let entrance = graph.add_entrance(position);
+                        clusters[cluster_index].entrances.push(entrance);
+                        clusters[other_cluster_index].entrances.push(entrance);
+                    }
+                }
+            }
+        }
+        for cluster_index in 0..clusters.len() {
+            graph.connect_region(&clusters, cluster_index);
+        }
+
+        HierarchicalNavigation {
+            bounds,
+            cluster_size,
+            columns,
+            rows,
+            clusters,
+            graph,
+        }
+    }
+    pub fn cluster_count(&self) -> usize {
+        self.clusters.len()
+    }
+    pub fn entrance_count(&self) -> usize {
+        self.graph.len()
+    }
+    fn cluster_at(&self, point: Vec2) -> Option<usize> {
+        if !self.bounds.contains(point) {
+            return None;
+        }
+        let column = (((point.x - self.bounds.min.x) / self.cluster_size) as usize).min(self.columns - 1);
+        let row = (((point.y - self.bounds.min.y) / self.cluster_size) as usize).min(self.rows - 1);
+        Some(row * self.columns + column)
+    }
+    pub fn find_path(&self, start: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
+        let start_cluster = self.cluster_at(start)?;
+        let end_cluster = self.cluster_at(end)?;
+        self.graph
+            .find_path(&self.clusters, (start, start_cluster), (end, end_cluster))
+    }
+}
+
+impl AbstractRegions for Vec<Cluster> {
+    type Region = usize;
+    fn entrances(&self, region: usize) -> &[usize] {
+        &self[region].entrances
+    }
+    fn local_path(&self, region: usize, start: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
+        let cluster = &self[region];
+        let path = cluster.navigation.find_path(start, end)?;
+        if path.iter().all(|&point| cluster.region.contains(point)) {
+            Some(path)
+        } else {
+            None
+        }
+    }
+}
+
+impl PathFinder for HierarchicalNavigation {
+    fn find_path(&self, start: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
+        self.find_path(start, end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Polygon;
+    use crate::test_utils::{path_crosses, square, square_grid};
+
+    fn bounds() -> Aabb {
+        Aabb::new(Vec2::new(0., 0.), Vec2::new(200., 200.))
+    }
+
+    #[test]
+    fn path_lengths_close_to_navigation() {
+        let mut obstacles = square_grid(Vec2::new(10., 10.), 6, 30., Vec2::new(1., 0.), |i, j| {
+            if (i + j) % 3 == 0 {
+                20.
+            } else {
+                12.
+            }
+        });
+        // A long wall across several clusters with a narrow door
+        obstacles.push(NavigationObstacle::new_polyline(vec![
+            Vec2::new(5., 105.),
+            Vec2::new(120., 105.),
+        ]));
+        obstacles.push(NavigationObstacle::new_polyline(vec![
+            Vec2::new(123., 105.),
+            Vec2::new(195., 105.),
+        ]));
+        let shapes: Vec<Shape> = obstacles.iter().map(|obstacle| obstacle.shape().clone()).collect();
+        let hierarchical = HierarchicalNavigation::new(obstacles.clone(), bounds(), 50.);
+        assert_eq!(hierarchical.cluster_count(), 16);
+        let navigation = Navigation::new(obstacles);
+        let points = [
+            Vec2::new(2., 2.),
+            Vec2::new(198., 198.),
+            Vec2::new(100., 3.),
+            Vec2::new(3., 150.),
+            Vec2::new(137., 96.),
+            Vec2::new(60., 120.),
+        ];
+        for &start in &points {
+            for &end in &points {
+                let optimal = path_length(&navigation.find_path(start, end).unwrap());
+                let path = hierarchical.find_path(start, end).unwrap();
+                assert_eq!((path[0], path[path.len() - 1]), (start, end));
+                assert!(!path_crosses(&path, &shapes));
+                let length = path_length(&path);
+                assert!(length >= optimal - 1e-9);
+                assert!(length <= optimal * 1.15, "{} > {} * 1.15", length, optimal);
+            }
+        }
+    }
+
+    #[test]
+    fn unreachable() {
+        // A closed room spanning four clusters
+        let room = Polygon::new(
+            square(25., 25., 50.).shape().clone(),
+            vec![square(30., 30., 40.).shape().clone()],
+        );
+        let room = NavigationObstacle::from_polygon(&room);
+        let hierarchical = HierarchicalNavigation::new(vec![room], bounds(), 50.);
+        let inside = Vec2::new(40., 40.);
+        assert_eq!(hierarchical.find_path(Vec2::new(10., 10.), inside), None);
+        assert_eq!(hierarchical.find_path(inside, Vec2::new(250., 10.)), None);
+        assert_eq!(
+            hierarchical.find_path(inside, Vec2::new(45., 35.)),
+            Some(vec![inside, Vec2::new(45., 35.)])
+        );
+        assert!(hierarchical.find_path(inside, Vec2::new(60., 60.)).is_some());
+    }
+}
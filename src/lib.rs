@@ -11,13 +11,22 @@ use crate::state::State;
 use core::mem;
 
 mod canvas;
+pub mod chunks;
 #[macro_use]
 mod console;
-mod a_star;
-mod geometry;
+pub mod a_star;
+pub mod contraction;
+pub mod geometry;
+pub mod hierarchical;
 mod input;
-mod navigation;
+pub mod lockstep;
+pub mod navigation;
+pub mod navmesh;
+mod path_cache;
+pub mod space_time;
 mod state;
+#[cfg(test)]
+mod test_utils;
 
 #[wasm_bindgen]
 extern "C" {
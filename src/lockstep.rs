@@ -0,0 +1,358 @@
+use std::cmp::Ordering;
+
+use bv::BitVec;
+use noisy_float::prelude::*;
+use noisy_float::types::N64;
+
+use crate::a_star::{a_star, AStarInput};
+use crate::geometry::{is_same_direction, is_tangent_at_vertex, Point, Scalar, Sign};
+use crate::navigation::{build_connections, node_position, Node};
+
+/// An obstacle of a `LockstepNavigation`.
+///
+/// There is no equivalent of `NavigationObstacle::expand`, since offsetting a polygon needs
+/// trigonometry. Expand the obstacles beforehand, and quantize the result before distributing it to
+/// the clients.
+pub struct LockstepObstacle<S> {
+    vertices: Vec<Point<S>>,
+    /// Same as `NavigationObstacle::concave_vertices`
+    concave_vertices: BitVec,
+}
+
+impl<S: Scalar> LockstepObstacle<S> {
+    pub fn new(vertices: Vec<Point<S>>) -> Self {
+        // The lowest vertex is always convex, so its orientation tells the winding order. Normalize
+        // it so that the inside of the obstacle is to the left of each segment (in a y-up
+        // coordinate system).
+        let lowest_vertex_index = (0..vertices.len()).min_by(|&index0, &index1| {
+            let (vertex0, vertex1) = (vertices[index0], vertices[index1]);
+            vertex0.y.compare(vertex1.y).then_with(|| vertex0.x.compare(vertex1.x))
+        });
+        let mut obstacle = LockstepObstacle {
+            vertices,
+            concave_vertices: BitVec::new(),
+        };
+        if let Some(lowest_vertex_index) = lowest_vertex_index {
+            if obstacle.turn(lowest_vertex_index) == Sign::Negative {
+                obstacle.vertices.reverse();
+            }
+        }
+        for vertex_index in 0..obstacle.vertices.len() {
+            let concave = obstacle.turn(vertex_index) == Sign::Positive;
+            obstacle.concave_vertices.push(concave);
+        }
+        obstacle
+    }
+    pub fn vertices(&self) -> &[Point<S>] {
+        &self.vertices
+    }
+    fn prev_vertex(&self, vertex_index: usize) -> Point<S> {
+        self.vertices[(vertex_index + self.vertices.len() - 1) % self.vertices.len()]
+    }
+    fn next_vertex(&self, vertex_index: usize) -> Point<S> {
+        self.vertices[(vertex_index + 1) % self.vertices.len()]
+    }
+    /// `Positive` if the outline turns left at the vertex.
+    fn turn(&self, vertex_index: usize) -> Sign {
+        S::orient(
+            self.prev_vertex(vertex_index),
+            self.vertices[vertex_index],
+            self.next_vertex(vertex_index),
+        )
+    }
+    fn segments(&self) -> impl Iterator<Item = (Point<S>, Point<S>)> + '_ {
+        (0..self.vertices.len()).map(move |vertex_index| (self.vertices[vertex_index], self.next_vertex(vertex_index)))
+    }
+    /// The equivalent of `is_in_connectable_range`.
+    fn is_in_connectable_range(&self, vertex_index: usize, target: Point<S>) -> bool {
+        // The outside of the obstacle is swept counterclockwise from the previous to the next vertex
+        is_tangent_at_vertex(
+            self.prev_vertex(vertex_index),
+            self.vertices[vertex_index],
+            self.next_vertex(vertex_index),
+            target,
+        )
+    }
+}
+
+fn ranges_overlap<S: Scalar>(a0: S, a1: S, b0: S, b1: S) -> bool {
+    let (a_min, a_max) = if a0.compare(a1) == Ordering::Greater {
+        (a1, a0)
+    } else {
+        (a0, a1)
+    };
+    let (b_min, b_max) = if b0.compare(b1) == Ordering::Greater {
+        (b1, b0)
+    } else {
+        (b0, b1)
+    };
+    a_min.compare(b_max) != Ordering::Greater && b_min.compare(a_max) != Ordering::Greater
+}
+
+/// The exact equivalent of `Segment::connective_intersect`.
+fn connective_intersect<S: Scalar>((a0, a1): (Point<S>, Point<S>), (b0, b1): (Point<S>, Point<S>)) -> bool {
+    if a0 == b0 {
+        is_same_direction(a0, a1, b1)
+    } else if a1 == b1 {
+        is_same_direction(a1, a0, b0)
+    } else if a0 == b1 {
+        is_same_direction(a0, a1, b0)
+    } else if a1 == b0 {
+        is_same_direction(a1, a0, b1)
+    } else {
+        let b0_side = S::orient(a0, a1, b0);
+        let b1_side = S::orient(a0, a1, b1);
+        let a0_side = S::orient(b0, b1, a0);
+        let a1_side = S::orient(b0, b1, a1);
+        if [b0_side, b1_side, a0_side, a1_side]
+            .iter()
+            .all(|&side| side == Sign::Zero)
+        {
+            // Collinear, so they overlap if their projections on both axes do
+            ranges_overlap(a0.x, a1.x, b0.x, b1.x) && ranges_overlap(a0.y, a1.y, b0.y, b1.y)
+        } else {
+            // Unless both endpoints of one segment are on the same side of the other
+            let same_side = |side0: Sign, side1: Sign| side0 != Sign::Zero && side0 == side1;
+            !same_side(b0_side, b1_side) && !same_side(a0_side, a1_side)
+        }
+    }
+}
+
+/// A navigation whose results are bit-identical on every platform, for lockstep multiplayer.
+///
+/// It is generic over the coordinate type, use `i32`, `i64` or `Fixed` coordinates to have every
+/// visibility decision made with exact integer arithmetic. Path costs are still computed with
+/// `f64`, but only with correctly rounded operations.
+pub struct LockstepNavigation<S> {
+    obstacles: Vec<LockstepObstacle<S>>,
+    navigation_graph: Vec<Node<Point<S>>>,
+}
+
+impl<S: Scalar> LockstepNavigation<S> {
+    pub fn new(obstacles: Vec<LockstepObstacle<S>>) -> Self {
+        let mut navigation = LockstepNavigation {
+            obstacles,
+            navigation_graph: vec![],
+        };
+        let concave_vertices: Vec<&BitVec> = navigation
+            .obstacles
+            .iter()
+            .map(|obstacle| &obstacle.concave_vertices)
+            .collect();
+        // The same graph as the one of `Navigation`, with exact predicates
+        let connections = build_connections(
+            &concave_vertices,
+            |(obstacle0_index, vertex0_index), (obstacle1_index, vertex1_index)| {
+                let obstacle0 = &navigation.obstacles[obstacle0_index];
+                let obstacle1 = &navigation.obstacles[obstacle1_index];
+                let vertex0 = obstacle0.vertices[vertex0_index];
+                let vertex1 = obstacle1.vertices[vertex1_index];
+                obstacle0.is_in_connectable_range(vertex0_index, vertex1)
+                    && obstacle1.is_in_connectable_range(vertex1_index, vertex0)
+                    && !navigation.intersects_with_obstacle(vertex0, vertex1)
+            },
+        );
+        let navigation_graph = navigation
+            .obstacles
+            .iter()
+            .flat_map(|obstacle| {
+                (0..obstacle.vertices.len()).map(move |vertex_index| {
+                    (
+                        obstacle.vertices[vertex_index],
+                        [obstacle.prev_vertex(vertex_index), obstacle.next_vertex(vertex_index)],
+                    )
+                })
+            })
+            .zip(connections)
+            .map(|((position, adjacent_vertices), connections)| {
+                Node::with_connections(connections, position, adjacent_vertices)
+            })
+            .collect();
+        navigation.navigation_graph = navigation_graph;
+        navigation
+    }
+    fn intersects_with_obstacle(&self, a: Point<S>, b: Point<S>) -> bool {
+        self.obstacles.iter().any(|obstacle| {
+            obstacle.segments().any(|segment| {
+                // A segment of an obstacle does not block itself
+                segment != (a, b) && segment != (b, a) && connective_intersect((a, b), segment)
+            })
+        })
+    }
+    /// See `Navigation::has_line_of_sight`.
+    pub fn has_line_of_sight(&self, a: Point<S>, b: Point<S>) -> bool {
+        a == b || !self.intersects_with_obstacle(a, b)
+    }
+    pub fn find_path(&self, start: Point<S>, end: Point<S>) -> Option<Vec<Point<S>>> {
+        if self.has_line_of_sight(start, end) {
+            return Some(vec![start, end]);
+        }
+        let mut node_id = 0;
+        let mut start_connections = vec![];
+        let mut end_candidates = BitVec::new_fill(false, self.navigation_graph.len() as u64);
+        for connecting_obstacle in &self.obstacles {
+            for (connecting_vertex_index, &connecting_vertex) in connecting_obstacle.vertices.iter().enumerate() {
+                node_id += 1;
+                if !connecting_obstacle.concave_vertices[connecting_vertex_index as u64] {
+                    // Skip convex
+                    continue;
+                }
+                if connecting_obstacle.is_in_connectable_range(connecting_vertex_index, start)
+                    && self.has_line_of_sight(start, connecting_vertex)
+                {
+                    start_connections.push(node_id - 1);
+                }
+                if connecting_obstacle.is_in_connectable_range(connecting_vertex_index, end)
+                    && self.has_line_of_sight(end, connecting_vertex)
+                {
+                    end_candidates.set((node_id - 1) as u64, true);
+                }
+            }
+        }
+        let a_star_input = LockstepAStarInput {
+            navigation_graph: &self.navigation_graph,
+            start_position: start,
+            start_connections,
+            end_position: end,
+            end_candidates,
+        };
+        a_star(&a_star_input).map(|path| {
+            path.into_iter()
+                .map(|node_id| a_star_input.get_node_position(node_id))
+                .collect()
+        })
+    }
+}
+
+struct LockstepAStarInput<'a, S> {
+    navigation_graph: &'a [Node<Point<S>>],
+    start_position: Point<S>,
+    start_connections: Vec<usize>,
+    end_position: Point<S>,
+    end_candidates: BitVec,
+}
+
+impl<S: Scalar> LockstepAStarInput<'_, S> {
+    fn get_node_position(&self, node_id: usize) -> Point<S> {
+        node_position(self.navigation_graph, self.start_position, self.end_position, node_id)
+    }
+}
+
+impl<S: Scalar> AStarInput for LockstepAStarInput<'_, S> {
+    fn neighbors(&self, node: usize) -> &[usize] {
+        if node == self.navigation_graph.len() {
+            &self.start_connections
+        } else {
+            self.navigation_graph[node].neighbors(self.end_candidates[node as u64])
+        }
+    }
+
+    fn distance(&self, from: usize, to: usize) -> N64 {
+        n64(self.get_node_position(from).dist(self.get_node_position(to)))
+    }
+
+    fn heuristic(&self, node: usize) -> N64 {
+        n64(self.get_node_position(node).dist(self.end_position))
+    }
+
+    fn len(&self) -> usize {
+        self.navigation_graph.len() + 2
+    }
+
+    fn start(&self) -> usize {
+        self.navigation_graph.len()
+    }
+
+    fn end(&self) -> usize {
+        self.navigation_graph.len() + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{Fixed, Vec2};
+    use crate::navigation::{Navigation, NavigationObstacle};
+
+    fn map<S: Scalar>(point: impl Fn(i32, i32) -> Point<S>) -> LockstepNavigation<S> {
+        let obstacles: Vec<Vec<(i32, i32)>> = vec![
+            vec![(10, 10), (20, 10), (20, 20), (10, 20)],
+            // Concave, and sharing an edge direction with the square
+            vec![(30, 0), (50, 0), (50, 30), (40, 30), (40, 10), (30, 10)],
+        ];
+        LockstepNavigation::new(
+            obstacles
+                .into_iter()
+                .map(|vertices| LockstepObstacle::new(vertices.into_iter().map(|(x, y)| point(x, y)).collect()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn same_path_as_navigation() {
+        let navigation = Navigation::new(vec![
+            NavigationObstacle::new(vec![(10, 10).into(), (20, 10).into(), (20, 20).into(), (10, 20).into()]),
+            NavigationObstacle::new(vec![
+                (30, 0).into(),
+                (50, 0).into(),
+                (50, 30).into(),
+                (40, 30).into(),
+                (40, 10).into(),
+                (30, 10).into(),
+            ]),
+        ]);
+        let lockstep = map(Point::new);
+        for &(start, end) in &[
+            ((0, 0), (30, 28)),
+            ((14, 5), (14, 25)),
+            ((0, 12), (60, 12)),
+            ((45, -5), (35, 20)),
+        ] {
+            let expected = navigation.find_path(start.into(), end.into());
+            let path = lockstep
+                .find_path(Point::new(start.0, start.1), Point::new(end.0, end.1))
+                .map(|path| path.into_iter().map(|point| Vec2::from((point.x, point.y))).collect());
+            assert_eq!(path, expected);
+        }
+    }
+
+    #[test]
+    fn scale_independent() {
+        let path = map(Point::new).find_path(Point::new(14, 5), Point::new(14, 25));
+        let scale = 1i64 << 40;
+        let large_path = map(|x, y| Point::new(i64::from(x) * scale, i64::from(y) * scale))
+            .find_path(Point::new(14 * scale, 5 * scale), Point::new(14 * scale, 25 * scale));
+        let fixed_path = map(|x, y| Point::new(Fixed::from_int(x), Fixed::from_int(y)))
+            .find_path(
+                Point::new(Fixed::from_int(14), Fixed::from_int(5)),
+                Point::new(Fixed::from_int(14), Fixed::from_int(25)),
+            )
+            .unwrap();
+        let path = path.unwrap();
+        assert_eq!(path.len(), 4);
+        assert_eq!(
+            large_path.unwrap(),
+            path.iter()
+                .map(|point| Point::new(i64::from(point.x) * scale, i64::from(point.y) * scale))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            fixed_path,
+            path.iter()
+                .map(|point| Point::new(Fixed::from_int(point.x), Fixed::from_int(point.y)))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn line_of_sight_is_exact() {
+        let navigation = map(Point::new);
+        // Ends at a corner of the square
+        assert!(navigation.has_line_of_sight(Point::new(0, 20), Point::new(10, 10)));
+        // Touches the same corner on the way
+        assert!(!navigation.has_line_of_sight(Point::new(0, 20), Point::new(30, -10)));
+        assert!(navigation.has_line_of_sight(Point::new(0, 20), Point::new(30, -11)));
+        // Runs along an edge
+        assert!(!navigation.has_line_of_sight(Point::new(0, 10), Point::new(30, 10)));
+    }
+}
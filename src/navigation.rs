@@ -1,15 +1,29 @@
+use std::collections::HashMap;
 use std::f64::consts::PI;
+use std::sync::Mutex;
 
 use bv::BitVec;
 use noisy_float::prelude::*;
 use noisy_float::types::N64;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
 
-use crate::a_star::{a_star, AStarInput};
-use crate::geometry::{Angle, Segment, Shape, ShapeWindingOrder, Vec2};
+use crate::a_star::{
+    dijkstra, AStarBudget, AStarFailure, AStarInput, AStarLimits, AStarSearch, AStarStatus, DStarLite,
+};
+use crate::geometry::{
+    boolean_operation, is_same_direction, is_tangent_at_vertex, orient2d, visibility_cone, visibility_polygon, Aabb,
+    Angle, BooleanOperation, Polygon, Segment, Shape, ShapeWindingOrder, Sign, Transform2, Vec2,
+};
+use crate::path_cache::PathCache;
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct NavigationObstacle {
     shape: Shape,
     concave_vertices: BitVec,
+    /// Whether the shape walks forward and back along an open polyline, see `new_polyline`.
+    is_polyline: bool,
 }
 
 impl NavigationObstacle {
@@ -20,15 +34,67 @@ impl NavigationObstacle {
         }
         let mut concave_vertices = BitVec::with_capacity(shape.vertices.len() as u64);
         for (vertex_index, vertex) in shape.vertices.iter().enumerate() {
-            let prev_direction = (shape.prev_vertex(vertex_index) - *vertex).direction();
-            let next_direction = (shape.next_vertex(vertex_index) - *vertex).direction();
-            let theta = next_direction - prev_direction; // Inner angle
-            concave_vertices.push(theta.as_radians() < PI);
+            // Whether the inner angle is less than PI, including the ends of polylines, where the
+            // inner angle is zero
+            let prev_vertex = shape.prev_vertex(vertex_index);
+            let next_vertex = shape.next_vertex(vertex_index);
+            let orientation = orient2d(prev_vertex, next_vertex, *vertex);
+            concave_vertices.push(
+                orientation == Sign::Positive
+                    || is_same_direction((*vertex).into(), prev_vertex.into(), next_vertex.into()),
+            );
         }
         NavigationObstacle {
             // shape: Shape::new(expanded_vertices),
             shape,
             concave_vertices,
+            is_polyline: false,
+        }
+    }
+    /// Like `new`, but first simplifies noisy outlines (e.g. traced from images) with
+    /// `Shape::simplify_douglas_peucker`, which reduces the number of nodes in the navigation graph.
+    pub fn new_simplified(vertices: Vec<Vec2>, tolerance: f64) -> Self {
+        NavigationObstacle::new(Shape::new(vertices).simplify_douglas_peucker(tolerance).vertices)
+    }
+    /// Creates an obstacle from a polygon with holes, see `Polygon::to_weakly_simple`. Holes can
+    /// not be reached from outside of the polygon, but paths within a hole are still found.
+    pub fn from_polygon(polygon: &Polygon) -> Self {
+        NavigationObstacle::new(polygon.to_weakly_simple().vertices)
+    }
+    /// Creates an obstacle from an open polyline, such as a fence or a river, which can not be
+    /// crossed but can be walked around at both ends. The polyline is stored as a closed shape of
+    /// zero area, going forward along the vertices and back again.
+    pub fn new_polyline(vertices: Vec<Vec2>) -> Self {
+        debug_assert!(vertices.len() >= 2);
+        let back = if vertices.len() > 2 {
+            vertices[1..vertices.len() - 1].iter().rev().copied().collect()
+        } else {
+            vec![]
+        };
+        NavigationObstacle {
+            is_polyline: true,
+            ..NavigationObstacle::new([vertices, back].concat())
+        }
+    }
+    /// Creates an obstacle from an open polyline with a thickness, with rounded ends and corners,
+    /// see `expand`.
+    pub fn new_thick_polyline(vertices: Vec<Vec2>, thickness: f64, resolution: f64) -> Self {
+        NavigationObstacle::new_polyline(vertices).expand(thickness / 2., resolution)
+    }
+    /// The outline of the obstacle, in `ShapeWindingOrder::Counterclockwise` order.
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+    /// For polylines, which of the two passes along the polyline a vertex is on, or `None` for its
+    /// two ends, which are on both. The passes are on opposite sides of the polyline.
+    fn polyline_pass(&self, vertex_index: usize) -> Option<bool> {
+        let mut ends = (0..self.shape.vertices.len())
+            .filter(|&index| self.shape.prev_vertex(index) == self.shape.next_vertex(index));
+        match (ends.next(), ends.next()) {
+            (Some(end0), Some(end1)) if vertex_index != end0 && vertex_index != end1 => {
+                Some(vertex_index > end0 && vertex_index < end1)
+            }
+            _ => None,
         }
     }
     pub fn expand(&self, delta: f64, resolution: f64) -> Self {
@@ -43,10 +109,20 @@ impl NavigationObstacle {
             let next_direction = next_vec.direction();
             let start_direction = prev_direction - Angle::from_radians_bounded(PI / 2.);
             let end_direction = next_direction + Angle::from_radians_bounded(PI / 2.);
-            let angle_diff = (start_direction - end_direction).as_radians();
+            // The ends of polylines are wrapped in half a circle
+            let is_end = is_same_direction(
+                (*vertex).into(),
+                self.shape.prev_vertex(vertex_index).into(),
+                self.shape.next_vertex(vertex_index).into(),
+            );
+            let angle_diff = if is_end {
+                PI
+            } else {
+                (start_direction - end_direction).as_radians()
+            };
             if angle_diff != 2. * PI {
                 // Do nothing if collinear
-                if angle_diff < PI {
+                if angle_diff < PI || is_end {
                     // Concave, create arc
                     let steps = (angle_diff / resolution).round().max(1.);
                     let step_angle = Angle::from_radians_bounded(angle_diff / steps);
@@ -68,11 +144,137 @@ impl NavigationObstacle {
         NavigationObstacle {
             shape: Shape::new(expanded_vertices),
             concave_vertices,
+            is_polyline: false,
+        }
+    }
+    /// Like `expand`, but expands each convex part of the obstacle (see `Shape::convex_decomposition`)
+    /// on its own and merges them again, so that concave obstacles do not end up self-intersecting.
+    /// Polylines are expanded segment by segment. The result may consist of multiple obstacles,
+    /// e.g. if a polyline was closed into a loop.
+    pub fn expand_decomposed(&self, delta: f64, resolution: f64) -> Vec<Self> {
+        let parts: Vec<Shape> = if self.is_polyline {
+            // The first half of the segments goes forward along the polyline
+            let segments_count = self.shape.vertices.len() / 2;
+            self.shape
+                .segments()
+                .into_iter()
+                .take(segments_count)
+                .map(|segment| Shape::new(vec![segment.p0, segment.p1]))
+                .collect()
+        } else {
+            self.shape.convex_decomposition()
+        };
+        let expanded_parts: Vec<Polygon> = parts
+            .into_iter()
+            .map(|part| {
+                NavigationObstacle::new(part.vertices)
+                    .expand(delta, resolution)
+                    .shape
+                    .into()
+            })
+            .collect();
+        boolean_operation(&expanded_parts, &[], BooleanOperation::Union)
+            .iter()
+            .map(NavigationObstacle::from_polygon)
+            .collect()
+    }
+    /// The unit normal of the segment starting at `segment_index`, pointing away from the obstacle.
+    fn outward_normal(&self, segment_index: usize) -> Vec2 {
+        // Vertices are always stored in `ShapeWindingOrder::Counterclockwise` order
+        let direction = self.shape.next_vertex(segment_index) - self.shape.vertices[segment_index];
+        Vec2::new(-direction.y, direction.x).normalize()
+    }
+    /// The direction pointing away from the obstacle at `point`, which lies on the segment starting
+    /// at `segment_index`. At vertices, the normals of both adjacent segments are averaged.
+    fn outward_normal_at(&self, segment_index: usize, point: Vec2) -> Vec2 {
+        let vertices_count = self.shape.vertices.len();
+        let (segment_index0, segment_index1) = if point == self.shape.vertices[segment_index] {
+            ((segment_index + vertices_count - 1) % vertices_count, segment_index)
+        } else if point == self.shape.next_vertex(segment_index) {
+            (segment_index, (segment_index + 1) % vertices_count)
+        } else {
+            return self.outward_normal(segment_index);
+        };
+        let normal = self.outward_normal(segment_index0) + self.outward_normal(segment_index1);
+        if normal == Vec2::zero() {
+            self.outward_normal(segment_index)
+        } else {
+            normal.normalize()
         }
     }
 }
 
-struct Node {
+/// Moves a point lying on the boundary of an obstacle slightly outwards, so that segments ending
+/// at it are not considered to intersect the obstacle.
+fn offset_from_boundary(point: Vec2, outward_normal: Vec2) -> Vec2 {
+    point + outward_normal * (point.magnitude().max(1.) * 1e-9)
+}
+
+/// How many directions `SightLines` sorts the obstacle edges into.
+const SIGHT_LINE_BINS: usize = 64;
+
+/// The obstacle edges around a viewpoint, sorted into bins by the directions they cover, so that
+/// checking the line of sight to a point only looks at the edges in its direction.
+struct SightLines {
+    viewpoint: Vec2,
+    bins: Vec<Vec<Segment>>,
+}
+
+impl SightLines {
+    fn new(viewpoint: Vec2, obstacles: &[NavigationObstacle]) -> Self {
+        let mut segments: Vec<Segment> = obstacles
+            .iter()
+            .flat_map(|obstacle| obstacle.shape.segments())
+            .collect();
+        // Edges close to the viewpoint block the most, so check them first
+        segments.sort_by(|segment0, segment1| {
+            let dist0 = segment0.closest_point(viewpoint).dist_squared(viewpoint);
+            let dist1 = segment1.closest_point(viewpoint).dist_squared(viewpoint);
+            dist0.partial_cmp(&dist1).unwrap()
+        });
+        let mut bins = vec![vec![]; SIGHT_LINE_BINS];
+        for segment in segments {
+            if orient2d(segment.p0, segment.p1, viewpoint) == Sign::Zero {
+                // In line with the viewpoint, possibly covering opposite directions
+                for bin in &mut bins {
+                    bin.push(segment);
+                }
+                continue;
+            }
+            let angle0 = (segment.p0 - viewpoint).atan2();
+            let mut span = (segment.p1 - viewpoint).atan2() - angle0;
+            if span > PI {
+                span -= 2. * PI;
+            } else if span <= -PI {
+                span += 2. * PI;
+            }
+            let first_angle = if span < 0. { angle0 + span } else { angle0 };
+            // One more bin on both sides, in case of rounding at the bin borders
+            let first_bin = Self::unbounded_bin(first_angle) - 1;
+            let last_bin = Self::unbounded_bin(first_angle + span.abs()) + 1;
+            for bin in first_bin..=last_bin {
+                bins[bin.rem_euclid(SIGHT_LINE_BINS as isize) as usize].push(segment);
+            }
+        }
+        SightLines { viewpoint, bins }
+    }
+    /// The bin of a direction given by its angle from the positive x axis, which is not wrapped
+    /// around for angles beyond `PI`.
+    fn unbounded_bin(angle: f64) -> isize {
+        ((angle + PI) / (2. * PI) * SIGHT_LINE_BINS as f64).floor() as isize
+    }
+    /// Same as `Navigation::has_line_of_sight` from the viewpoint to `point`.
+    fn can_see(&self, point: Vec2) -> bool {
+        let bin = Self::unbounded_bin((point - self.viewpoint).atan2()).rem_euclid(SIGHT_LINE_BINS as isize);
+        let segment = Segment::new(point, self.viewpoint);
+        !self.bins[bin as usize]
+            .iter()
+            .any(|edge| segment.connective_intersect(edge))
+    }
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub(crate) struct Node<P = Vec2> {
     /// # Important
     /// Since connections stored here are shared between multiple path finding sessions, there need
     /// to be a way for us to dynamically inject starting point and ending point into this Vec.
@@ -84,7 +286,86 @@ struct Node {
     /// during the actual path finding. It is possible to do so, because the node_id of the ending
     /// point is always len() + 1.
     connections: Vec<usize>,
-    position: Vec2,
+    position: P,
+    /// The previous and the next vertex of the obstacle, which a path has to turn around here.
+    adjacent_vertices: [P; 2],
+}
+
+impl Node {
+    fn new(obstacle: &NavigationObstacle, vertex_index: usize) -> Self {
+        Node::with_connections(
+            vec![],
+            obstacle.shape.vertices[vertex_index],
+            [
+                obstacle.shape.prev_vertex(vertex_index),
+                obstacle.shape.next_vertex(vertex_index),
+            ],
+        )
+    }
+}
+
+impl<P: Copy> Node<P> {
+    pub(crate) fn with_connections(connections: Vec<usize>, position: P, adjacent_vertices: [P; 2]) -> Self {
+        Node {
+            connections,
+            position,
+            adjacent_vertices,
+        }
+    }
+    /// The nodes that a path can continue to from here, see docs for field `connections`.
+    pub(crate) fn neighbors(&self, connects_to_end: bool) -> &[usize] {
+        if connects_to_end {
+            &self.connections
+        } else {
+            // If it does not connect to the ending point, exclude the ending point
+            &self.connections[1..]
+        }
+    }
+}
+
+/// The position of a node of `navigation_graph`, or of the starting or the ending point that come
+/// after its nodes, see docs for field `Node::connections`.
+pub(crate) fn node_position<P: Copy>(navigation_graph: &[Node<P>], start: P, end: P, node_id: usize) -> P {
+    if node_id == navigation_graph.len() {
+        start
+    } else if node_id == navigation_graph.len() + 1 {
+        end
+    } else {
+        navigation_graph[node_id].position
+    }
+}
+
+/// The `connections` of the node of every vertex, in order of obstacles and vertices. Obstacles are
+/// given by which of their vertices are concave, only concave vertices get connected, and only if
+/// `is_taut_edge` holds for them. Vertices are given by the index of their obstacle and their index
+/// within the obstacle.
+pub(crate) fn build_connections(
+    concave_vertices: &[&BitVec],
+    mut is_taut_edge: impl FnMut((usize, usize), (usize, usize)) -> bool,
+) -> Vec<Vec<usize>> {
+    let vertices: Vec<(usize, usize)> = concave_vertices
+        .iter()
+        .enumerate()
+        .flat_map(|(obstacle_index, concave_vertices)| {
+            (0..concave_vertices.len() as usize).map(move |vertex_index| (obstacle_index, vertex_index))
+        })
+        .collect();
+    let nodes_count = vertices.len();
+    let mut connections = vec![vec![]; nodes_count];
+    for (node0_id, &vertex0) in vertices.iter().enumerate() {
+        if !concave_vertices[vertex0.0][vertex0.1 as u64] {
+            continue;
+        }
+        // See docs for field `Node::connections`
+        connections[node0_id].push(nodes_count + 1);
+        for (node1_id, &vertex1) in vertices[..node0_id].iter().enumerate() {
+            if concave_vertices[vertex1.0][vertex1.1 as u64] && is_taut_edge(vertex0, vertex1) {
+                connections[node0_id].push(node1_id);
+                connections[node1_id].push(node0_id);
+            }
+        }
+    }
+    connections
 }
 
 type NavigationGraph = Vec<Node>;
@@ -95,125 +376,466 @@ pub struct ShapeVertexIndex {
     vertex_index: usize,
 }
 
+/// Answers path queries, regardless of how the free space is represented, see `Navigation` and
+/// `NavMesh`.
+pub trait PathFinder {
+    /// Finds a path from `start` to `end` around the obstacles, including both end points.
+    fn find_path(&self, start: Vec2, end: Vec2) -> Option<Vec<Vec2>>;
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Navigation {
     obstacles: Vec<NavigationObstacle>,
     navigation_graph: NavigationGraph,
+    /// The node id of the first vertex of every obstacle. Consecutive, unless obstacles have been
+    /// removed with `remove_obstacle`, which leaves their nodes behind without any edges.
+    first_node_ids: Vec<usize>,
+    landmarks: Option<Landmarks>,
+    /// See `enable_path_cache`. Behind a lock, since `find_path` only borrows the navigation.
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    path_cache: Mutex<Option<PathCache>>,
 }
 
-fn bound_angle(angle: f64) -> f64 {
-    if angle > PI {
-        angle - 2. * PI
-    } else if angle < -PI {
-        angle + 2. * PI
-    } else {
-        angle
-    }
+/// Graph distances from a few nodes to every other node, used for the ALT (A*, landmarks, triangle
+/// inequality) heuristic. The distance between two points is at least the difference between
+/// their distances to any landmark.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+struct Landmarks {
+    nodes: Vec<usize>,
+    /// `distances[landmark][node]`, or `None` if the node cannot be reached from the landmark.
+    distances: Vec<Vec<Option<f64>>>,
 }
 
 fn is_in_connectable_range(obstacle: &NavigationObstacle, vertex: Vec2, vertex_index: usize, target: Vec2) -> bool {
-    let a = (obstacle.shape.prev_vertex(vertex_index) - vertex).atan2();
-    let b = (obstacle.shape.next_vertex(vertex_index) - vertex).atan2();
-    let c = (target - vertex).atan2();
-    let p = a <= b;
-    let q = b <= c;
-    let r = a < c;
-    let ap = bound_angle(a + PI);
-    let bp = bound_angle(b + PI);
-    let s = ap <= bp;
-    let t = bp <= c;
-    let u = ap < c;
-    p ^ q ^ r && s ^ t ^ u
+    // Vertices are stored in `ShapeWindingOrder::Counterclockwise` order, so the outside of the
+    // obstacle is swept from the next to the previous vertex
+    is_tangent_at_vertex(
+        obstacle.shape.next_vertex(vertex_index).into(),
+        vertex.into(),
+        obstacle.shape.prev_vertex(vertex_index).into(),
+        target.into(),
+    )
 }
 
-impl Navigation {
-    fn build_navigation_graph(obstacles: &[NavigationObstacle]) -> NavigationGraph {
-        // To build the navigation graph, we cast a line from each vertex0 to every other vertex1,
-        // and find intersections with each intersecting_segment.
-        let nodes_count = obstacles
-            .iter()
-            .fold(0, |count, obstacle| count + obstacle.shape.vertices.len());
-        let mut navigation_graph: NavigationGraph = Vec::with_capacity(nodes_count);
-        for (obstacle0_index, obstacle0) in obstacles.iter().enumerate() {
-            for (vertex0_index, vertex0) in obstacle0.shape.vertices.iter().enumerate() {
-                // Every vertex0
-                let mut node = Node {
-                    connections: vec![],
-                    position: *vertex0,
-                };
-                if !obstacle0.concave_vertices[vertex0_index as u64] {
-                    navigation_graph.push(node);
-                } else {
-                    // Only continue if concave
-                    // See docs for field `connections`
-                    node.connections.push(nodes_count + 1);
-                    let mut node1_id = 0;
-                    for (obstacle1_index, obstacle1) in obstacles[..(obstacle0_index + 1)].iter().enumerate() {
-                        let vertex1_slice = if obstacle0_index == obstacle1_index {
-                            &obstacle1.shape.vertices[..vertex0_index]
-                        } else {
-                            &obstacle1.shape.vertices
-                        };
-                        'next_vertex: for (vertex1_index, vertex1) in vertex1_slice.iter().enumerate() {
-                            // To any other vertex1
-                            node1_id += 1;
-
-                            if !obstacle1.concave_vertices[vertex1_index as u64] {
-                                // If convex, just skip over
-                                continue;
-                            }
+/// Whether a shortest path can go straight from one concave vertex to another, each given by the
+/// index of its obstacle and its index within the obstacle.
+fn is_taut_edge(obstacles: &[NavigationObstacle], vertex0: (usize, usize), vertex1: (usize, usize)) -> bool {
+    // The special cases below expect vertex1 to come first
+    let ((obstacle0_index, vertex0_index), (obstacle1_index, vertex1_index)) = if vertex0 < vertex1 {
+        (vertex1, vertex0)
+    } else {
+        (vertex0, vertex1)
+    };
+    let obstacle0 = &obstacles[obstacle0_index];
+    let obstacle1 = &obstacles[obstacle1_index];
+    let vertex0 = obstacle0.shape.vertices[vertex0_index];
+    let vertex1 = obstacle1.shape.vertices[vertex1_index];
 
-                            if !is_in_connectable_range(obstacle0, *vertex0, vertex0_index, *vertex1) {
-                                continue;
-                            }
-                            if !is_in_connectable_range(obstacle1, *vertex1, vertex1_index, *vertex0) {
-                                continue;
-                            }
+    // A shortest path only follows an edge that is tangent to the obstacles at both ends, otherwise
+    // it could be shortened by cutting the corner. This is the same rule as
+    // `NavigationAStarInput::is_successor`.
+    if !is_in_connectable_range(obstacle0, vertex0, vertex0_index, vertex1) {
+        return false;
+    }
+    if !is_in_connectable_range(obstacle1, vertex1, vertex1_index, vertex0) {
+        return false;
+    }
+    // Going from one side of a polyline to the other, which `is_blocked_by_segment` does not catch
+    // when both vertices are on the same edge
+    if obstacle0_index == obstacle1_index && obstacle0.is_polyline {
+        if let (Some(pass0), Some(pass1)) = (
+            obstacle0.polyline_pass(vertex0_index),
+            obstacle0.polyline_pass(vertex1_index),
+        ) {
+            if pass0 != pass1 {
+                return false;
+            }
+        }
+    }
 
-                            let segment = Segment::new(*vertex0, *vertex1);
-
-                            for (intersecting_obstacle_index, intersecting_obstacle) in obstacles.iter().enumerate() {
-                                for (intersecting_segment_index, intersecting_segment) in
-                                    intersecting_obstacle.shape.segments().into_iter().enumerate()
-                                {
-                                    if obstacle0_index == obstacle1_index
-                                        && obstacle1_index == intersecting_obstacle_index
-                                    {
-                                        // All same obstacle
-                                        if vertex0_index - 1 == vertex1_index
-                                            && intersecting_segment_index == vertex1_index
-                                        {
-                                            continue;
-                                        }
-                                        if vertex0_index == obstacle0.shape.vertices.len() - 1
-                                            && vertex1_index == 0
-                                            && intersecting_segment_index == obstacle0.shape.vertices.len() - 1
-                                        {
-                                            continue;
-                                        }
-                                    }
-                                    if segment.connective_intersect(&intersecting_segment) {
-                                        continue 'next_vertex;
-                                    }
-                                }
-                            }
-                            node.connections.push(node1_id - 1);
-                            let node0_id = navigation_graph.len();
-                            navigation_graph[node1_id - 1].connections.push(node0_id);
-                        }
-                    }
-                    navigation_graph.push(node);
+    let segment = Segment::new(vertex0, vertex1);
+    for (intersecting_obstacle_index, intersecting_obstacle) in obstacles.iter().enumerate() {
+        for (intersecting_segment_index, intersecting_segment) in
+            intersecting_obstacle.shape.segments().into_iter().enumerate()
+        {
+            if obstacle0_index == obstacle1_index && obstacle1_index == intersecting_obstacle_index {
+                // All same obstacle
+                if vertex0_index - 1 == vertex1_index && intersecting_segment_index == vertex1_index {
+                    continue;
+                }
+                if vertex0_index == obstacle0.shape.vertices.len() - 1
+                    && vertex1_index == 0
+                    && intersecting_segment_index == obstacle0.shape.vertices.len() - 1
+                {
+                    continue;
                 }
             }
+            if is_blocked_by_segment(intersecting_obstacle, intersecting_segment, segment) {
+                return false;
+            }
         }
-        navigation_graph
+    }
+    true
+}
+
+fn is_blocked_by_segment(obstacle: &NavigationObstacle, obstacle_segment: Segment, segment: Segment) -> bool {
+    if obstacle.is_polyline && obstacle_segment == segment {
+        // Walking along a polyline, where the other side has the same edge
+        return false;
+    }
+    segment.connective_intersect(&obstacle_segment)
+}
+
+fn is_blocked_by_obstacle(obstacle: &NavigationObstacle, segment: Segment) -> bool {
+    obstacle
+        .shape
+        .segments()
+        .into_iter()
+        .any(|obstacle_segment| is_blocked_by_segment(obstacle, obstacle_segment, segment))
+}
+
+impl Navigation {
+    fn build_navigation_graph(obstacles: &[NavigationObstacle]) -> NavigationGraph {
+        let concave_vertices: Vec<&BitVec> = obstacles.iter().map(|obstacle| &obstacle.concave_vertices).collect();
+        let connections = build_connections(&concave_vertices, |vertex0, vertex1| {
+            is_taut_edge(obstacles, vertex0, vertex1)
+        });
+        obstacles
+            .iter()
+            .flat_map(|obstacle| {
+                (0..obstacle.shape.vertices.len()).map(move |vertex_index| Node::new(obstacle, vertex_index))
+            })
+            .zip(connections)
+            .map(|(node, connections)| Node { connections, ..node })
+            .collect()
+    }
+    /// The id of the node of the first vertex of every obstacle, for a freshly built graph.
+    fn first_node_ids(obstacles: &[NavigationObstacle]) -> Vec<usize> {
+        obstacles
+            .iter()
+            .scan(0, |node_id, obstacle| {
+                let first_node_id = *node_id;
+                *node_id += obstacle.shape.vertices.len();
+                Some(first_node_id)
+            })
+            .collect()
     }
     pub fn new(obstacles: Vec<NavigationObstacle>) -> Self {
         let navigation_graph = Navigation::build_navigation_graph(&obstacles);
         Navigation {
+            first_node_ids: Navigation::first_node_ids(&obstacles),
             obstacles,
             navigation_graph,
+            landmarks: None,
+            path_cache: Mutex::new(None),
         }
     }
+    /// Replaces all obstacles and builds the navigation graph again. Landmarks are precomputed
+    /// again and cached paths are forgotten.
+    pub fn update_obstacles(&mut self, obstacles: Vec<NavigationObstacle>) {
+        self.navigation_graph = Navigation::build_navigation_graph(&obstacles);
+        self.first_node_ids = Navigation::first_node_ids(&obstacles);
+        self.obstacles = obstacles;
+        self.graph_updated();
+        if let Some(landmarks) = self.landmarks.take() {
+            self.precompute_landmarks(landmarks.nodes.len());
+        }
+    }
+    /// The current obstacles, in the order that `RayHit::obstacle_index` and
+    /// `Clearance::obstacle_index` refer to.
+    pub fn obstacles(&self) -> &[NavigationObstacle] {
+        &self.obstacles
+    }
+    /// Adds an obstacle without building the whole navigation graph again. Only the edges crossing
+    /// the new obstacle and the edges to its vertices are updated. The nodes of the new obstacle
+    /// are added after all others, so existing nodes keep their ids.
+    ///
+    /// Landmarks are forgotten, since the new nodes would only get the straight-line distance,
+    /// and mixing both makes `find_path` miss the shortest path. Call `precompute_landmarks` again
+    /// once all obstacles have been added.
+    ///
+    /// Returns the edges that have been added or removed, see `DynamicPath::update`.
+    pub fn add_obstacle(&mut self, obstacle: NavigationObstacle) -> Vec<(usize, usize)> {
+        let mut changed_edges = vec![];
+        for node0_id in 0..self.navigation_graph.len() {
+            let blocked_node_ids: Vec<usize> = self
+                .graph_neighbors(node0_id)
+                .iter()
+                .copied()
+                .filter(|&node1_id| {
+                    node0_id < node1_id
+                        && is_blocked_by_obstacle(
+                            &obstacle,
+                            Segment::new(
+                                self.navigation_graph[node0_id].position,
+                                self.navigation_graph[node1_id].position,
+                            ),
+                        )
+                })
+                .collect();
+            for node1_id in blocked_node_ids {
+                self.disconnect(node0_id, node1_id);
+                changed_edges.push((node0_id, node1_id));
+            }
+        }
+
+        let first_node_id = self.navigation_graph.len();
+        let nodes_count = first_node_id + obstacle.shape.vertices.len();
+        // The end point comes after the new nodes, see docs for field `connections`
+        for node in &mut self.navigation_graph {
+            if let Some(end_node_id) = node.connections.first_mut() {
+                *end_node_id = nodes_count + 1;
+            }
+        }
+        for vertex_index in 0..obstacle.shape.vertices.len() {
+            let mut node = Node::new(&obstacle, vertex_index);
+            if obstacle.concave_vertices[vertex_index as u64] {
+                node.connections.push(nodes_count + 1);
+            }
+            self.navigation_graph.push(node);
+        }
+        self.first_node_ids.push(first_node_id);
+        self.obstacles.push(obstacle);
+
+        let obstacle_index = self.obstacles.len() - 1;
+        let concave_vertices = self.concave_vertices();
+        for &(obstacle0_index, vertex0_index, node0_id) in &concave_vertices {
+            if obstacle0_index != obstacle_index {
+                continue;
+            }
+            for &(obstacle1_index, vertex1_index, node1_id) in &concave_vertices {
+                if node1_id < node0_id
+                    && is_taut_edge(
+                        &self.obstacles,
+                        (obstacle0_index, vertex0_index),
+                        (obstacle1_index, vertex1_index),
+                    )
+                {
+                    self.connect(node0_id, node1_id);
+                    changed_edges.push((node1_id, node0_id));
+                }
+            }
+        }
+        self.landmarks = None;
+        self.graph_updated();
+        changed_edges
+    }
+    /// Removes an obstacle without building the whole navigation graph again. Only the edges to
+    /// its vertices and the edges it was in the way of are updated. All other nodes keep their
+    /// ids, and the nodes of the removed obstacle stay in the graph without any edges. They are not
+    /// reused by `add_obstacle`, only `update_obstacles` numbers the nodes from scratch, so call it
+    /// now and then when adding and removing many obstacles.
+    ///
+    /// Landmarks are forgotten, since the new edges can make paths shorter than they estimate.
+    /// Call `precompute_landmarks` again once all obstacles have been removed.
+    ///
+    /// Like `Vec::remove`, this shifts the index of every later obstacle down by one. Obstacles
+    /// added with `add_obstacle` come after all others.
+    ///
+    /// Returns the edges that have been added or removed, see `DynamicPath::update`.
+    pub fn remove_obstacle(&mut self, obstacle_index: usize) -> Vec<(usize, usize)> {
+        let mut changed_edges = vec![];
+        let first_node_id = self.first_node_ids.remove(obstacle_index);
+        let obstacle = self.obstacles.remove(obstacle_index);
+        for node0_id in first_node_id..(first_node_id + obstacle.shape.vertices.len()) {
+            for node1_id in self.graph_neighbors(node0_id).to_vec() {
+                self.disconnect(node0_id, node1_id);
+                changed_edges.push((node0_id.min(node1_id), node0_id.max(node1_id)));
+            }
+            self.navigation_graph[node0_id].connections.clear();
+        }
+
+        let concave_vertices = self.concave_vertices();
+        for (index, &(obstacle0_index, vertex0_index, node0_id)) in concave_vertices.iter().enumerate() {
+            let position0 = self.navigation_graph[node0_id].position;
+            for &(obstacle1_index, vertex1_index, node1_id) in &concave_vertices[(index + 1)..] {
+                let segment = Segment::new(position0, self.navigation_graph[node1_id].position);
+                if is_blocked_by_obstacle(&obstacle, segment)
+                    && !self.graph_neighbors(node0_id).contains(&node1_id)
+                    && is_taut_edge(
+                        &self.obstacles,
+                        (obstacle0_index, vertex0_index),
+                        (obstacle1_index, vertex1_index),
+                    )
+                {
+                    self.connect(node0_id, node1_id);
+                    changed_edges.push((node0_id.min(node1_id), node0_id.max(node1_id)));
+                }
+            }
+        }
+        self.landmarks = None;
+        self.graph_updated();
+        changed_edges
+    }
+    /// Every concave vertex, with the index of its obstacle, its index within the obstacle and its
+    /// node id.
+    fn concave_vertices(&self) -> Vec<(usize, usize, usize)> {
+        let mut concave_vertices = vec![];
+        for (obstacle_index, obstacle) in self.obstacles.iter().enumerate() {
+            for vertex_index in 0..obstacle.shape.vertices.len() {
+                if obstacle.concave_vertices[vertex_index as u64] {
+                    concave_vertices.push((
+                        obstacle_index,
+                        vertex_index,
+                        self.first_node_ids[obstacle_index] + vertex_index,
+                    ));
+                }
+            }
+        }
+        concave_vertices
+    }
+    fn connect(&mut self, node0_id: usize, node1_id: usize) {
+        self.navigation_graph[node0_id].connections.push(node1_id);
+        self.navigation_graph[node1_id].connections.push(node0_id);
+    }
+    fn disconnect(&mut self, node0_id: usize, node1_id: usize) {
+        // The end point at the front is never a node of the graph, so it is kept
+        self.navigation_graph[node0_id]
+            .connections
+            .retain(|&node_id| node_id != node1_id);
+        self.navigation_graph[node1_id]
+            .connections
+            .retain(|&node_id| node_id != node0_id);
+    }
+    /// Forgets the cached paths, which may no longer be the shortest or free.
+    fn graph_updated(&mut self) {
+        if let Some(path_cache) = self.path_cache.get_mut().unwrap() {
+            path_cache.clear();
+        }
+    }
+    /// Precomputes the graph distances to `count` landmarks, which `find_path` then combines with
+    /// the straight-line distance into a tighter heuristic. This pays off in maze-like maps, where
+    /// the straight-line distance badly underestimates the length of the path.
+    ///
+    /// Landmarks are picked one after another as the node farthest from the ones picked so far,
+    /// within the largest connected part of the graph. Elsewhere, only the straight-line distance
+    /// is used.
+    ///
+    /// Takes a search through the whole graph for every landmark and for every connected part of
+    /// the graph, and stores `count` distances per node.
+    pub fn precompute_landmarks(&mut self, count: usize) {
+        let graph_input = self.a_star_input_without_endpoints();
+        let graph_len = self.navigation_graph.len();
+        let graph_distances = |node_id: usize| -> Vec<Option<f64>> {
+            dijkstra(&graph_input, node_id)
+                .into_iter()
+                .take(graph_len)
+                .map(|distance| distance.map(|distance| distance.raw()))
+                .collect()
+        };
+        // Nodes with at least one connection besides the end point
+        let candidates: Vec<usize> = (0..graph_len)
+            .filter(|&node_id| self.navigation_graph[node_id].connections.len() > 1)
+            .collect();
+        let mut visited: BitVec = BitVec::new_fill(false, graph_len as u64);
+        let (mut largest_size, mut largest_distances) = (0, vec![]);
+        for &node_id in &candidates {
+            if visited[node_id as u64] {
+                continue;
+            }
+            let distances = graph_distances(node_id);
+            let mut size = 0;
+            for (reached_node_id, distance) in distances.iter().enumerate() {
+                if distance.is_some() {
+                    visited.set(reached_node_id as u64, true);
+                    size += 1;
+                }
+            }
+            if size > largest_size {
+                largest_size = size;
+                largest_distances = distances;
+            }
+        }
+        // Start from the node farthest from an arbitrary one, which is on the edge of the map
+        let mut min_distances: Vec<f64> = largest_distances
+            .into_iter()
+            .map(|distance| distance.unwrap_or(f64::NEG_INFINITY))
+            .collect();
+        let mut nodes: Vec<usize> = vec![];
+        let mut distances = vec![];
+        while nodes.len() < count {
+            let landmark = candidates
+                .iter()
+                .copied()
+                .filter(|&node_id| min_distances[node_id] > 0. && !nodes.contains(&node_id))
+                .max_by(|&node0, &node1| min_distances[node0].partial_cmp(&min_distances[node1]).unwrap());
+            let landmark = match landmark {
+                Some(landmark) => landmark,
+                None => break,
+            };
+            let landmark_distances = graph_distances(landmark);
+            for (min_distance, distance) in min_distances.iter_mut().zip(&landmark_distances) {
+                *min_distance = if nodes.is_empty() {
+                    distance.unwrap_or(f64::NEG_INFINITY)
+                } else {
+                    min_distance.min(distance.unwrap_or(f64::NEG_INFINITY))
+                };
+            }
+            nodes.push(landmark);
+            distances.push(landmark_distances);
+        }
+        self.landmarks = Some(Landmarks { nodes, distances });
+    }
+    /// Forgets the landmarks of `precompute_landmarks`, going back to the straight-line heuristic.
+    pub fn clear_landmarks(&mut self) {
+        self.landmarks = None;
+    }
+    pub fn landmark_count(&self) -> usize {
+        self.landmarks.as_ref().map_or(0, |landmarks| landmarks.nodes.len())
+    }
+    /// Makes `find_path` remember the paths of the last `capacity` queries, by the cells of a grid
+    /// of `cell_size` that the start and the end point fall into. A later query from and to the
+    /// same cells reuses the remembered path, as long as its actual start and end point can see the
+    /// first and the last waypoint in between. Such a path may be slightly longer than a freshly
+    /// found one.
+    ///
+    /// Useful when many agents go to the same place. The cache is emptied whenever the obstacles
+    /// change, and is neither serialized nor kept by `transformed`.
+    pub fn enable_path_cache(&mut self, capacity: usize, cell_size: f64) {
+        *self.path_cache.get_mut().unwrap() = Some(PathCache::new(capacity, cell_size));
+    }
+    pub fn disable_path_cache(&mut self) {
+        *self.path_cache.get_mut().unwrap() = None;
+    }
+    pub fn cached_path_count(&self) -> usize {
+        self.path_cache.lock().unwrap().as_ref().map_or(0, PathCache::len)
+    }
+    /// A copy of this navigation with every obstacle transformed, reusing the navigation graph
+    /// instead of building it again. Whether two vertices can see each other does not change under
+    /// an affine transform, as long as it does not mirror the obstacles. Returns `None` if
+    /// `transform` mirrors or flattens them.
+    pub fn transformed(&self, transform: &Transform2) -> Option<Self> {
+        if transform.determinant() <= 0. {
+            return None;
+        }
+        Some(Navigation {
+            obstacles: self
+                .obstacles
+                .iter()
+                .map(|obstacle| NavigationObstacle {
+                    shape: obstacle.shape.transform(transform),
+                    concave_vertices: obstacle.concave_vertices.clone(),
+                    is_polyline: obstacle.is_polyline,
+                })
+                .collect(),
+            navigation_graph: self
+                .navigation_graph
+                .iter()
+                .map(|node| Node {
+                    connections: node.connections.clone(),
+                    position: transform.apply(node.position),
+                    adjacent_vertices: [
+                        transform.apply(node.adjacent_vertices[0]),
+                        transform.apply(node.adjacent_vertices[1]),
+                    ],
+                })
+                .collect(),
+            first_node_ids: self.first_node_ids.clone(),
+            // Distances are not preserved by every affine transform
+            landmarks: None,
+            path_cache: Mutex::new(None),
+        })
+    }
 }
 
 struct NavigationAStarInput<'a> {
@@ -222,17 +844,16 @@ struct NavigationAStarInput<'a> {
     start_connections: Vec<usize>,
     end_position: Vec2,
     end_candidates: BitVec,
+    landmarks: Option<&'a Landmarks>,
+    /// The graph distance from every landmark to the end point.
+    end_landmark_distances: Vec<Option<f64>>,
+    /// See `FindPathOptions::taut_successors`.
+    taut_successors: bool,
 }
 
 impl NavigationAStarInput<'_> {
     fn get_node_position(&self, node_id: usize) -> Vec2 {
-        if node_id == self.navigation_graph.len() {
-            self.start_position
-        } else if node_id == self.navigation_graph.len() + 1 {
-            self.end_position
-        } else {
-            self.navigation_graph[node_id].position
-        }
+        node_position(self.navigation_graph, self.start_position, self.end_position, node_id)
     }
 }
 
@@ -241,13 +862,7 @@ impl AStarInput for NavigationAStarInput<'_> {
         if node == self.navigation_graph.len() {
             &self.start_connections
         } else {
-            let connections = &self.navigation_graph[node].connections;
-            if self.end_candidates[node as u64] {
-                connections
-            } else {
-                // If it does not connect to the ending point, exclude the ending point
-                &connections[1..]
-            }
+            self.navigation_graph[node].neighbors(self.end_candidates[node as u64])
         }
     }
 
@@ -256,13 +871,50 @@ impl AStarInput for NavigationAStarInput<'_> {
     }
 
     fn heuristic(&self, node: usize) -> N64 {
-        n64(self.get_node_position(node).dist(self.end_position))
+        let mut heuristic = self.get_node_position(node).dist(self.end_position);
+        if let (Some(landmarks), true) = (self.landmarks, node < self.navigation_graph.len()) {
+            for (distances, end_distance) in landmarks.distances.iter().zip(&self.end_landmark_distances) {
+                if let (Some(distance), Some(end_distance)) = (distances[node], end_distance) {
+                    heuristic = heuristic.max((end_distance - distance).abs());
+                }
+            }
+        }
+        n64(heuristic)
     }
 
     fn len(&self) -> usize {
         self.navigation_graph.len() + 2
     }
 
+    fn is_successor(&self, parent: usize, node: usize, neighbor: usize) -> bool {
+        if !self.taut_successors || node >= self.navigation_graph.len() {
+            return true;
+        }
+        if neighbor == parent {
+            return false;
+        }
+        // The edge from the parent is tangent to the obstacle, which thus lies on one side of it.
+        // A shortest path only wraps around the node by turning towards that side.
+        let from = self.get_node_position(parent);
+        let node = &self.navigation_graph[node];
+        let side_of = |point: Vec2| {
+            // Shortcut for points at the same position, which `orient2d` would decide exactly
+            if from == node.position || point == from || point == node.position {
+                Sign::Zero
+            } else {
+                orient2d(from, node.position, point)
+            }
+        };
+        let [prev_vertex, next_vertex] = node.adjacent_vertices;
+        // When arriving along an edge of the obstacle, only the other edge tells the side
+        let obstacle_side = match side_of(prev_vertex) {
+            Sign::Zero => side_of(next_vertex),
+            side => side,
+        };
+        let turn = side_of(self.get_node_position(neighbor));
+        obstacle_side == Sign::Zero || turn == Sign::Zero || turn == obstacle_side
+    }
+
     fn start(&self) -> usize {
         self.navigation_graph.len()
     }
@@ -283,45 +935,777 @@ impl Navigation {
         }
         false
     }
-    pub fn find_path(&self, start: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
-        if !self.intersects_with_obstacle(Segment::new(start, end)) {
-            return Some(vec![start, end]);
+    /// Whether the segment between `a` and `b` does not cross any obstacle. Touching an obstacle
+    /// at a vertex is fine, the same as for the paths returned by `find_path`.
+    pub fn has_line_of_sight(&self, a: Vec2, b: Vec2) -> bool {
+        !self.intersects_with_obstacle(Segment::new(a, b))
+    }
+    /// Casts a ray from `origin` towards `direction`, and finds the first obstacle edge it hits
+    /// within `max_dist`.
+    pub fn raycast(&self, origin: Vec2, direction: Vec2, max_dist: f64) -> Option<RayHit> {
+        if direction == Vec2::zero() {
+            return None;
         }
-        let mut node_id = 0;
-        let mut start_connections = vec![];
-        let mut end_candidates = BitVec::new_fill(false, self.navigation_graph.len() as u64);
-        for connecting_obstacle in &self.obstacles {
+        let direction = direction.normalize();
+        let ray = Segment::new(origin, origin + direction * max_dist);
+        let mut closest_hit: Option<RayHit> = None;
+        for (obstacle_index, obstacle) in self.obstacles.iter().enumerate() {
+            if let Some((edge_index, point)) = obstacle.shape.closest_intersection(&ray) {
+                let distance = origin.dist(point);
+                if !matches!(closest_hit, Some(closest_hit) if closest_hit.distance <= distance) {
+                    let normal = obstacle.outward_normal_at(edge_index, point);
+                    closest_hit = Some(RayHit {
+                        point,
+                        distance,
+                        obstacle_index,
+                        edge_index,
+                        // Face the ray, even if it is cast from inside an obstacle
+                        normal: if normal.dot(direction) > 0. {
+                            normal * -1.
+                        } else {
+                            normal
+                        },
+                    });
+                }
+            }
+        }
+        closest_hit
+    }
+    /// Finds the obstacle edge closest to `point`. Returns `None` if there are no obstacles.
+    pub fn clearance_at(&self, point: Vec2) -> Option<Clearance> {
+        self.clearance_of_segment(Segment::new(point, point))
+    }
+    /// Finds the minimum clearance along each segment of `path`, such as one returned by
+    /// `find_path`. Clearances are measured against the obstacles of this navigation, so the paths
+    /// of a navigation built from expanded obstacles have to be checked against a navigation built
+    /// from the original ones to verify the reserved width.
+    pub fn path_clearance(&self, path: &[Vec2]) -> Vec<Option<Clearance>> {
+        path.windows(2)
+            .map(|points| self.clearance_of_segment(Segment::new(points[0], points[1])))
+            .collect()
+    }
+    fn clearance_of_segment(&self, segment: Segment) -> Option<Clearance> {
+        let mut closest_clearance: Option<Clearance> = None;
+        for (obstacle_index, obstacle) in self.obstacles.iter().enumerate() {
+            for (edge_index, edge) in obstacle.shape.segments().into_iter().enumerate() {
+                let (segment_point, edge_point) = segment.closest_points(&edge);
+                let distance = segment_point.dist(edge_point);
+                if !matches!(closest_clearance, Some(closest_clearance) if closest_clearance.distance <= distance) {
+                    closest_clearance = Some(Clearance {
+                        distance,
+                        point: edge_point,
+                        obstacle_index,
+                        edge_index,
+                    });
+                }
+            }
+        }
+        closest_clearance
+    }
+    /// Computes the polygon of all points within `bounds` that are visible from `viewpoint`. See
+    /// `geometry::visibility_polygon`.
+    pub fn visibility_polygon(&self, viewpoint: Vec2, bounds: Aabb) -> Shape {
+        visibility_polygon(viewpoint, &self.internal_obstacles(), bounds)
+    }
+    /// Same as `visibility_polygon`, but restricted to directions within `half_angle` (in radians)
+    /// of `direction`. See `geometry::visibility_cone`.
+    pub fn visibility_cone(&self, viewpoint: Vec2, bounds: Aabb, direction: Vec2, half_angle: f64) -> Shape {
+        visibility_cone(viewpoint, &self.internal_obstacles(), bounds, direction, half_angle)
+    }
+    fn is_inside_obstacle(&self, point: Vec2) -> bool {
+        self.obstacles.iter().any(|obstacle| obstacle.shape.contains(point))
+    }
+    /// Moves a point lying inside an obstacle to the closest point outside of all obstacles. Points
+    /// that are not inside any obstacle are returned unchanged.
+    pub fn nearest_free_point(&self, point: Vec2) -> Vec2 {
+        if !self.is_inside_obstacle(point) {
+            return point;
+        }
+        let mut candidates = vec![];
+        for obstacle in &self.obstacles {
+            for (segment_index, segment) in obstacle.shape.segments().into_iter().enumerate() {
+                let edge_point = segment.closest_point(point);
+                candidates.push((
+                    edge_point.dist_squared(point),
+                    offset_from_boundary(edge_point, obstacle.outward_normal_at(segment_index, edge_point)),
+                ));
+            }
+        }
+        candidates.sort_by(|(dist0, _), (dist1, _)| dist0.partial_cmp(dist1).unwrap());
+        let closest_free_edge_point = candidates
+            .iter()
+            .position(|(_, candidate)| !self.is_inside_obstacle(*candidate));
+        if closest_free_edge_point == Some(0) {
+            return candidates[0].1;
+        }
+
+        // The closest points of some edges are covered by other obstacles, so the nearest free point
+        // may also be where the boundaries of two obstacles cross
+        let mut candidates: Vec<(f64, Vec2)> = closest_free_edge_point
+            .map(|index| candidates[index])
+            .into_iter()
+            .collect();
+        for (obstacle0_index, obstacle0) in self.obstacles.iter().enumerate() {
+            for obstacle1 in &self.obstacles[(obstacle0_index + 1)..] {
+                for (segment0_index, segment0) in obstacle0.shape.segments().into_iter().enumerate() {
+                    for (segment1_index, segment1) in obstacle1.shape.segments().into_iter().enumerate() {
+                        if let Some(intersection) = segment0.intersect(&segment1) {
+                            let normal = obstacle0.outward_normal_at(segment0_index, intersection)
+                                + obstacle1.outward_normal_at(segment1_index, intersection);
+                            if normal == Vec2::zero() {
+                                continue;
+                            }
+                            candidates.push((
+                                intersection.dist_squared(point),
+                                offset_from_boundary(intersection, normal.normalize()),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        candidates.sort_by(|(dist0, _), (dist1, _)| dist0.partial_cmp(dist1).unwrap());
+        candidates
+            .into_iter()
+            .map(|(_, candidate)| candidate)
+            .find(|candidate| !self.is_inside_obstacle(*candidate))
+            .unwrap_or(point)
+    }
+    /// The nodes of the navigation graph that a path from `point` can continue to.
+    pub(crate) fn connectable_nodes(&self, point: Vec2) -> Vec<usize> {
+        let mut connectable_nodes = vec![];
+        for (connecting_obstacle, &first_node_id) in self.obstacles.iter().zip(&self.first_node_ids) {
             for (connecting_vertex_index, connecting_vertex) in connecting_obstacle.shape.vertices.iter().enumerate() {
-                node_id += 1;
                 if !connecting_obstacle.concave_vertices[connecting_vertex_index as u64] {
                     // Skip convex
                     continue;
                 }
-                if is_in_connectable_range(connecting_obstacle, *connecting_vertex, connecting_vertex_index, start)
-                    && !self.intersects_with_obstacle(Segment::new(start, *connecting_vertex))
-                {
-                    start_connections.push(node_id - 1);
-                }
-                if is_in_connectable_range(connecting_obstacle, *connecting_vertex, connecting_vertex_index, end)
-                    && !self.intersects_with_obstacle(Segment::new(end, *connecting_vertex))
+                if is_in_connectable_range(connecting_obstacle, *connecting_vertex, connecting_vertex_index, point)
+                    && !self.intersects_with_obstacle(Segment::new(point, *connecting_vertex))
                 {
-                    end_candidates.set((node_id - 1) as u64, true);
+                    connectable_nodes.push(first_node_id + connecting_vertex_index);
                 }
             }
         }
-        let a_star_input = NavigationAStarInput {
+        connectable_nodes
+    }
+    pub(crate) fn graph_len(&self) -> usize {
+        self.navigation_graph.len()
+    }
+    pub(crate) fn graph_position(&self, node_id: usize) -> Vec2 {
+        self.navigation_graph[node_id].position
+    }
+    /// The nodes connected to a node of the navigation graph, without the end point.
+    pub(crate) fn graph_neighbors(&self, node_id: usize) -> &[usize] {
+        self.navigation_graph[node_id].connections.get(1..).unwrap_or(&[])
+    }
+    fn a_star_input(&self, start: Vec2, end: Vec2) -> NavigationAStarInput<'_> {
+        let start_connections = self.connectable_nodes(start);
+        let mut end_candidates = BitVec::new_fill(false, self.navigation_graph.len() as u64);
+        for node_id in self.connectable_nodes(end) {
+            end_candidates.set(node_id as u64, true);
+        }
+        let landmarks = self.landmarks.as_ref();
+        let end_landmark_distances = landmarks
+            .map(|landmarks| {
+                landmarks
+                    .distances
+                    .iter()
+                    .map(|distances| {
+                        (0..self.navigation_graph.len())
+                            .filter(|&node_id| end_candidates[node_id as u64])
+                            .filter_map(|node_id| {
+                                distances[node_id]
+                                    .map(|distance| distance + self.navigation_graph[node_id].position.dist(end))
+                            })
+                            .min_by(|distance0, distance1| distance0.partial_cmp(distance1).unwrap())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        NavigationAStarInput {
             navigation_graph: &self.navigation_graph,
             start_position: start,
             start_connections,
             end_position: end,
             end_candidates,
-        };
-        a_star(&a_star_input).map(|vec| {
-            vec.into_iter()
-                .map(|node_id| a_star_input.get_node_position(node_id))
-                .collect()
+            landmarks,
+            end_landmark_distances,
+            taut_successors: false,
+        }
+    }
+    /// The navigation graph alone, where neither the start nor the end point is connected.
+    fn a_star_input_without_endpoints(&self) -> NavigationAStarInput<'_> {
+        NavigationAStarInput {
+            navigation_graph: &self.navigation_graph,
+            start_position: Vec2::zero(),
+            start_connections: vec![],
+            end_position: Vec2::zero(),
+            end_candidates: BitVec::new_fill(false, self.navigation_graph.len() as u64),
+            landmarks: None,
+            end_landmark_distances: vec![],
+            taut_successors: false,
+        }
+    }
+    /// Starts searching for the cheapest paths from `root` to every other point, see `PathTree`.
+    pub fn path_tree(&self, root: Vec2) -> PathTree {
+        let root_connections = self.connectable_nodes(root);
+        let search = AStarSearch::new(&self.path_tree_input(root, &root_connections));
+        PathTree {
+            root,
+            root_connections,
+            search,
+        }
+    }
+    fn path_tree_input(&self, root: Vec2, root_connections: &[usize]) -> PathTreeInput<'_> {
+        PathTreeInput(NavigationAStarInput {
+            start_position: root,
+            start_connections: root_connections.to_vec(),
+            ..self.a_star_input_without_endpoints()
         })
     }
+    /// Starts searching for a path from `start` to `goal` that can be repaired as the start point
+    /// moves and obstacles change, see `DynamicPath`.
+    pub fn dynamic_path(&self, start: Vec2, goal: Vec2) -> DynamicPath {
+        let endpoints = DynamicPathEndpoints {
+            goal,
+            agent: start,
+            goal_connections: self.connectable_nodes(goal),
+            agent_connections: self.connectable_nodes(start),
+            goal_visible: self.has_line_of_sight(goal, start),
+        };
+        DynamicPath {
+            graph_len: self.navigation_graph.len(),
+            search: DStarLite::new(&endpoints.input(self, vec![])),
+            endpoints,
+        }
+    }
+    /// Prepares a path search that can be advanced a bit at a time with `PathSearch::step`.
+    pub fn start_search(&self, start: Vec2, end: Vec2) -> PathSearch<'_> {
+        self.start_search_with_options(start, end, &FindPathOptions::default())
+    }
+    pub fn start_search_with_options(&self, start: Vec2, end: Vec2, options: &FindPathOptions) -> PathSearch<'_> {
+        let (start, end) = if options.snap_endpoints {
+            (self.nearest_free_point(start), self.nearest_free_point(end))
+        } else {
+            (start, end)
+        };
+        let a_star = if self.intersects_with_obstacle(Segment::new(start, end)) {
+            let mut a_star_input = self.a_star_input(start, end);
+            a_star_input.taut_successors = options.taut_successors;
+            let a_star_search = AStarSearch::with_limits(
+                &a_star_input,
+                AStarLimits {
+                    max_cost: options.max_cost.map(n64),
+                    max_expansions: options.max_expansions,
+                },
+            );
+            Some((a_star_input, a_star_search))
+        } else {
+            None
+        };
+        PathSearch {
+            navigation: self,
+            start,
+            end,
+            options: options.clone(),
+            a_star,
+            cancelled: false,
+        }
+    }
+    pub fn find_path(&self, start: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
+        let find_path = || {
+            self.find_path_with_options(start, end, &FindPathOptions::default())
+                .ok()
+                .map(|path| path.points)
+        };
+        // Not holding the lock while searching
+        let cached = self.path_cache.lock().unwrap().as_mut().map(|path_cache| {
+            let key = path_cache.key(start, end);
+            (key, path_cache.get(key))
+        });
+        let (key, cached_path) = match cached {
+            Some(cached) => cached,
+            None => return find_path(),
+        };
+        if let Some(path) = cached_path.and_then(|cached_path| self.reuse_cached_path(&cached_path, start, end)) {
+            return Some(path);
+        }
+        let path = find_path()?;
+        if let Some(path_cache) = self.path_cache.lock().unwrap().as_mut() {
+            path_cache.insert(key, path.clone());
+        }
+        Some(path)
+    }
+    /// `cached_path` with its end points replaced by `start` and `end`, if they can see the first
+    /// and the last waypoint in between.
+    fn reuse_cached_path(&self, cached_path: &[Vec2], start: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
+        if self.has_line_of_sight(start, end) {
+            return Some(vec![start, end]);
+        }
+        let waypoints = &cached_path[1..cached_path.len() - 1];
+        match (waypoints.first(), waypoints.last()) {
+            (Some(&first), Some(&last))
+                if self.has_line_of_sight(start, first) && self.has_line_of_sight(last, end) =>
+            {
+                Some([&[start], waypoints, &[end]].concat())
+            }
+            _ => None,
+        }
+    }
+    /// Like `find_path`, but reports why no path has been found.
+    pub fn find_path_with_options(
+        &self,
+        start: Vec2,
+        end: Vec2,
+        options: &FindPathOptions,
+    ) -> Result<NavigationPath, AStarFailure> {
+        match self
+            .start_search_with_options(start, end, options)
+            .step(AStarBudget::Unlimited)
+        {
+            PathSearchStatus::Found(path) => Ok(path),
+            PathSearchStatus::Failed(failure) => Err(failure),
+            PathSearchStatus::Pending => unreachable!(),
+        }
+    }
+    /// Finds the path to the location closest to the end point that has been reached by a failed
+    /// search. Candidates are the nodes reached by the search, and the points on obstacle edges
+    /// that are closest to the end point and visible from one of these nodes.
+    fn closest_reachable_path(
+        &self,
+        a_star_input: &NavigationAStarInput,
+        a_star_search: &AStarSearch,
+        max_cost: Option<f64>,
+    ) -> NavigationPath {
+        let end = a_star_input.end_position;
+        let mut reached_nodes: Vec<(usize, f64)> = (0..a_star_input.len())
+            .filter(|&node_id| node_id != a_star_input.end())
+            .filter_map(|node_id| a_star_search.g_score(node_id).map(|g_score| (node_id, g_score.raw())))
+            // Nodes that have been pruned for exceeding the cost limit still have a g-score
+            .filter(|&(_, g_score)| !matches!(max_cost, Some(max_cost) if g_score > max_cost))
+            .collect();
+        reached_nodes.sort_by(|(_, g_score0), (_, g_score1)| g_score0.partial_cmp(g_score1).unwrap());
+        let (closest_node, closest_dist) = reached_nodes
+            .iter()
+            .map(|&(node_id, _)| (node_id, a_star_input.get_node_position(node_id).dist(end)))
+            .min_by(|(_, dist0), (_, dist1)| dist0.partial_cmp(dist1).unwrap())
+            .unwrap();
+        let mut closest_path = a_star_search.path_to(closest_node);
+        let mut closest_edge_point = None;
+
+        let mut edge_points = vec![];
+        for obstacle in &self.obstacles {
+            for (segment_index, segment) in obstacle.shape.segments().into_iter().enumerate() {
+                let edge_point = segment.closest_point(end);
+                let dist = edge_point.dist(end);
+                if dist < closest_dist {
+                    // Step off the edge so that it does not count as an intersection
+                    let edge_point =
+                        offset_from_boundary(edge_point, obstacle.outward_normal_at(segment_index, edge_point));
+                    edge_points.push((dist, edge_point));
+                }
+            }
+        }
+        edge_points.sort_by(|(dist0, _), (dist1, _)| dist0.partial_cmp(dist1).unwrap());
+        'next_edge_point: for (_, edge_point) in edge_points {
+            let sight_lines = SightLines::new(edge_point, &self.obstacles);
+            for &(node_id, g_score) in &reached_nodes {
+                let node_position = a_star_input.get_node_position(node_id);
+                if matches!(max_cost, Some(max_cost) if g_score + node_position.dist(edge_point) > max_cost) {
+                    continue;
+                }
+                if sight_lines.can_see(node_position) {
+                    closest_path = a_star_search.path_to(node_id);
+                    closest_edge_point = Some(edge_point);
+                    break 'next_edge_point;
+                }
+            }
+        }
+
+        let mut points: Vec<Vec2> = closest_path
+            .into_iter()
+            .map(|node_id| a_star_input.get_node_position(node_id))
+            .collect();
+        points.extend(closest_edge_point);
+        NavigationPath { points, partial: true }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub point: Vec2,
+    /// Distance from the origin of the ray to `point`
+    pub distance: f64,
+    /// Index into the current obstacles of the navigation, see `Navigation::remove_obstacle`
+    pub obstacle_index: usize,
+    /// Index of the hit edge, which starts at the vertex with the same index
+    pub edge_index: usize,
+    /// Unit normal of the hit edge, facing towards the origin of the ray
+    pub normal: Vec2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Clearance {
+    /// Distance to the closest obstacle edge
+    pub distance: f64,
+    /// The closest point on that edge
+    pub point: Vec2,
+    /// Index into the current obstacles of the navigation, see `Navigation::remove_obstacle`
+    pub obstacle_index: usize,
+    pub edge_index: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FindPathOptions {
+    /// Give up on paths longer than this.
+    pub max_cost: Option<f64>,
+    /// Give up after expanding this many nodes.
+    pub max_expansions: Option<usize>,
+    /// Instead of failing, return the path to the location closest to the end point that could be
+    /// reached. That location is either a node of the navigation graph or a point on an obstacle
+    /// edge.
+    pub closest_if_unreachable: bool,
+    /// Move endpoints that are inside an obstacle to the nearest point outside of all obstacles.
+    /// See `Navigation::nearest_free_point`.
+    pub snap_endpoints: bool,
+    /// When continuing from a node, skip the neighbors that the path can not turn to without
+    /// becoming longer than necessary, given the edge it arrived on. Finds equally short paths
+    /// while considering fewer edges.
+    pub taut_successors: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavigationPath {
+    pub points: Vec<Vec2>,
+    /// Whether the path stops short of the requested end point. Only possible with
+    /// `FindPathOptions::closest_if_unreachable`.
+    pub partial: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathSearchStatus {
+    Pending,
+    Found(NavigationPath),
+    Failed(AStarFailure),
+}
+
+/// A path search whose progress is kept between calls to `step`, so that a single query can be
+/// spread over multiple frames. Dropping it or calling `cancel` abandons the search.
+pub struct PathSearch<'a> {
+    navigation: &'a Navigation,
+    start: Vec2,
+    end: Vec2,
+    options: FindPathOptions,
+    /// `None` if both endpoints can directly see each other
+    a_star: Option<(NavigationAStarInput<'a>, AStarSearch)>,
+    cancelled: bool,
+}
+
+impl PathSearch<'_> {
+    pub fn step(&mut self, budget: AStarBudget) -> PathSearchStatus {
+        if self.cancelled {
+            return PathSearchStatus::Failed(AStarFailure::Cancelled);
+        }
+        if let Some((a_star_input, a_star_search)) = &mut self.a_star {
+            match a_star_search.step(a_star_input, budget) {
+                AStarStatus::Pending => PathSearchStatus::Pending,
+                AStarStatus::Found(path) => PathSearchStatus::Found(NavigationPath {
+                    points: path
+                        .into_iter()
+                        .map(|node_id| a_star_input.get_node_position(node_id))
+                        .collect(),
+                    partial: false,
+                }),
+                AStarStatus::Failed(failure) => {
+                    if self.options.closest_if_unreachable {
+                        PathSearchStatus::Found(self.navigation.closest_reachable_path(
+                            a_star_input,
+                            a_star_search,
+                            self.options.max_cost,
+                        ))
+                    } else {
+                        PathSearchStatus::Failed(failure)
+                    }
+                }
+            }
+        } else {
+            match self.options.max_cost {
+                Some(max_cost) if self.start.dist(self.end) > max_cost => {
+                    if self.options.closest_if_unreachable {
+                        // Go as far as possible towards the end point
+                        PathSearchStatus::Found(NavigationPath {
+                            points: vec![self.start, self.start + (self.end - self.start).normalize() * max_cost],
+                            partial: true,
+                        })
+                    } else {
+                        PathSearchStatus::Failed(AStarFailure::CostLimitReached)
+                    }
+                }
+                _ => PathSearchStatus::Found(NavigationPath {
+                    points: vec![self.start, self.end],
+                    partial: false,
+                }),
+            }
+        }
+    }
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+        self.a_star = None;
+    }
+}
+
+/// The cheapest paths from a fixed root to any point, for when only the other end point keeps
+/// moving, such as a target following the mouse. The search from the root is kept between queries
+/// and only continues as far as needed, instead of starting over for every new end point.
+///
+/// Since paths can be walked both ways, a tree rooted at a fixed end point serves a moving start
+/// point just as well, see `path_from`. The tree has to be created again whenever the root or the
+/// obstacles change.
+pub struct PathTree {
+    root: Vec2,
+    root_connections: Vec<usize>,
+    /// A search without heuristic and without end point, which expands the nodes in the order of
+    /// their distance from the root
+    search: AStarSearch,
+}
+
+/// The navigation graph with the root of a `PathTree` as the start point, searched without
+/// heuristic.
+struct PathTreeInput<'a>(NavigationAStarInput<'a>);
+
+impl AStarInput for PathTreeInput<'_> {
+    fn neighbors(&self, node: usize) -> &[usize] {
+        self.0.neighbors(node)
+    }
+    fn distance(&self, from: usize, to: usize) -> N64 {
+        self.0.distance(from, to)
+    }
+    fn heuristic(&self, _node: usize) -> N64 {
+        n64(0.)
+    }
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+    fn start(&self) -> usize {
+        self.0.start()
+    }
+    fn end(&self) -> usize {
+        self.0.end()
+    }
+}
+
+impl PathTree {
+    pub fn root(&self) -> Vec2 {
+        self.root
+    }
+    /// The cheapest path from the root to `end`, which is as short as the one `find_path` finds.
+    /// `navigation` has to be the one that created the tree.
+    pub fn path_to(&mut self, navigation: &Navigation, end: Vec2) -> Option<Vec<Vec2>> {
+        if navigation.has_line_of_sight(self.root, end) {
+            return Some(vec![self.root, end]);
+        }
+        let input = navigation.path_tree_input(self.root, &self.root_connections);
+        let end_connections = navigation.connectable_nodes(end);
+        let cheapest_end_connection = |search: &AStarSearch| {
+            end_connections
+                .iter()
+                .filter_map(|&node_id| {
+                    let g_score = search.g_score(node_id)?;
+                    Some((
+                        node_id,
+                        g_score + n64(navigation.navigation_graph[node_id].position.dist(end)),
+                    ))
+                })
+                .min_by_key(|&(_, cost)| cost)
+        };
+        // Every node that has not been expanded yet is at least as far from the root as the next
+        // one in the queue
+        loop {
+            match (self.search.min_open_f_score(), cheapest_end_connection(&self.search)) {
+                (Some(min_open_f_score), Some((_, cost))) if min_open_f_score >= cost => break,
+                (Some(_), _) => {
+                    self.search.step(&input, AStarBudget::Iterations(1));
+                }
+                (None, _) => break,
+            }
+        }
+        let (node_id, _) = cheapest_end_connection(&self.search)?;
+        let mut path = vec![self.root];
+        path.extend(
+            self.search
+                .path_to(node_id)
+                .into_iter()
+                // Without the root
+                .skip(1)
+                .map(|node_id| navigation.navigation_graph[node_id].position),
+        );
+        path.push(end);
+        Some(path)
+    }
+    /// The cheapest path from `start` to the root, see `path_to`.
+    pub fn path_from(&mut self, navigation: &Navigation, start: Vec2) -> Option<Vec<Vec2>> {
+        let mut path = self.path_to(navigation, start)?;
+        path.reverse();
+        Some(path)
+    }
+}
+
+/// A path from a moving agent to a fixed goal, which is repaired when the agent moves or obstacles
+/// are added or removed, instead of being searched again from scratch. Only the part of the search
+/// affected by the changes is redone (D* Lite), which is much cheaper when a door closes somewhere
+/// along the way.
+pub struct DynamicPath {
+    endpoints: DynamicPathEndpoints,
+    /// The length of the navigation graph, after which the goal and the agent are numbered
+    graph_len: usize,
+    search: DStarLite,
+}
+
+/// The end points of a `DynamicPath` and how they are connected to the navigation graph.
+struct DynamicPathEndpoints {
+    goal: Vec2,
+    agent: Vec2,
+    goal_connections: Vec<usize>,
+    agent_connections: Vec<usize>,
+    goal_visible: bool,
+}
+
+/// The navigation graph with the goal of a `DynamicPath` as the start point and the agent as the
+/// end point. Unlike `NavigationAStarInput`, edges to both end points can be walked both ways.
+struct DynamicPathInput<'a> {
+    navigation: &'a Navigation,
+    goal: Vec2,
+    agent: Vec2,
+    /// The neighbors of the goal, the agent and of every node connected to them. Other nodes have
+    /// the neighbors of the navigation graph.
+    neighbors: HashMap<usize, Vec<usize>>,
+    changed_edges: Vec<(usize, usize)>,
+}
+impl DynamicPathInput<'_> {
+    fn position(&self, node_id: usize) -> Vec2 {
+        node_position(&self.navigation.navigation_graph, self.goal, self.agent, node_id)
+    }
+}
+
+impl AStarInput for DynamicPathInput<'_> {
+    fn neighbors(&self, node: usize) -> &[usize] {
+        match self.neighbors.get(&node) {
+            Some(neighbors) => neighbors,
+            None => self.navigation.graph_neighbors(node),
+        }
+    }
+    fn distance(&self, from: usize, to: usize) -> N64 {
+        n64(self.position(from).dist(self.position(to)))
+    }
+    fn heuristic(&self, node: usize) -> N64 {
+        n64(self.position(node).dist(self.agent))
+    }
+    fn len(&self) -> usize {
+        self.navigation.navigation_graph.len() + 2
+    }
+    fn start(&self) -> usize {
+        self.navigation.navigation_graph.len()
+    }
+    fn end(&self) -> usize {
+        self.navigation.navigation_graph.len() + 1
+    }
+    fn changed_edges(&self) -> &[(usize, usize)] {
+        &self.changed_edges
+    }
+}
+
+impl DynamicPathEndpoints {
+    fn input<'a>(&self, navigation: &'a Navigation, changed_edges: Vec<(usize, usize)>) -> DynamicPathInput<'a> {
+        let goal_id = navigation.navigation_graph.len();
+        let agent_id = goal_id + 1;
+        let mut neighbors: HashMap<usize, Vec<usize>> = HashMap::new();
+        neighbors.insert(goal_id, vec![]);
+        neighbors.insert(agent_id, vec![]);
+        let mut connect = |node0_id: usize, node1_id: usize| {
+            for (from, to) in [(node0_id, node1_id), (node1_id, node0_id)] {
+                neighbors
+                    .entry(from)
+                    .or_insert_with(|| navigation.graph_neighbors(from).to_vec())
+                    .push(to);
+            }
+        };
+        for &node_id in &self.goal_connections {
+            connect(goal_id, node_id);
+        }
+        for &node_id in &self.agent_connections {
+            connect(agent_id, node_id);
+        }
+        if self.goal_visible {
+            connect(goal_id, agent_id);
+        }
+        DynamicPathInput {
+            navigation,
+            goal: self.goal,
+            agent: self.agent,
+            neighbors,
+            changed_edges,
+        }
+    }
+}
+
+impl DynamicPath {
+    pub fn goal(&self) -> Vec2 {
+        self.endpoints.goal
+    }
+    /// The cheapest path from the agent to the goal, as short as the one `find_path` finds.
+    /// `navigation` has to be the one that created the path, with all changes since passed to
+    /// `update`.
+    pub fn path(&self, navigation: &Navigation) -> Option<Vec<Vec2>> {
+        let input = self.endpoints.input(navigation, vec![]);
+        let path = self.search.path(&input)?;
+        Some(path.into_iter().map(|node_id| input.position(node_id)).collect())
+    }
+    /// Moves the agent to `agent` and repairs the path after the navigation graph has changed by
+    /// `changed_edges`, as returned by `Navigation::add_obstacle` and `Navigation::remove_obstacle`.
+    /// Returns the new path from the agent to the goal.
+    pub fn update(
+        &mut self,
+        navigation: &Navigation,
+        agent: Vec2,
+        changed_edges: &[(usize, usize)],
+    ) -> Option<Vec<Vec2>> {
+        let graph_changed = !changed_edges.is_empty() || navigation.navigation_graph.len() != self.graph_len;
+        let goal_id = navigation.navigation_graph.len();
+        let agent_id = goal_id + 1;
+        let endpoints = &mut self.endpoints;
+        let mut changed_edges = changed_edges.to_vec();
+        // Edges to the agent change their cost whenever it moves, and any of them may have appeared
+        // or disappeared
+        let agent_connections = navigation.connectable_nodes(agent);
+        for &node_id in endpoints.agent_connections.iter().chain(&agent_connections) {
+            changed_edges.push((node_id, agent_id));
+        }
+        endpoints.agent_connections = agent_connections;
+        if graph_changed {
+            // New obstacles may be in the way of the goal, or removed ones may no longer be
+            let goal_connections = navigation.connectable_nodes(endpoints.goal);
+            for &node_id in endpoints.goal_connections.iter().chain(&goal_connections) {
+                changed_edges.push((node_id, goal_id));
+            }
+            endpoints.goal_connections = goal_connections;
+        }
+        endpoints.goal_visible = navigation.has_line_of_sight(endpoints.goal, agent);
+        changed_edges.push((goal_id, agent_id));
+
+        let heuristic_change = n64(endpoints.agent.dist(agent));
+        endpoints.agent = agent;
+        self.graph_len = navigation.navigation_graph.len();
+        self.search
+            .update(&self.endpoints.input(navigation, changed_edges), heuristic_change);
+        self.path(navigation)
+    }
+}
+
+impl PathFinder for Navigation {
+    fn find_path(&self, start: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
+        Navigation::find_path(self, start, end)
+    }
 }
 
 /// Only use this trait if you want to access the internals of a Navigation struct
@@ -348,3 +1732,737 @@ impl NavigationInternal for Navigation {
         self.obstacles.iter().map(|obstacle| &obstacle.shape).collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::path_length;
+    use crate::test_utils::{scattered_polygons, square, Random};
+
+    #[test]
+    fn find_path_around_square() {
+        let navigation = Navigation::new(vec![square(10., 10., 10.)]);
+        let path = navigation.find_path(Vec2::new(0., 12.), Vec2::new(30., 12.)).unwrap();
+        assert_eq!(
+            path,
+            vec![
+                Vec2::new(0., 12.),
+                Vec2::new(10., 10.),
+                Vec2::new(20., 10.),
+                Vec2::new(30., 12.)
+            ]
+        );
+    }
+
+    #[test]
+    fn find_path_around_collinear_vertices() {
+        // The vertex in the middle of the left edge is not a node, and does not stop its neighbour
+        // from being one
+        let navigation = Navigation::new(vec![NavigationObstacle::new(vec![
+            Vec2::new(10., 10.),
+            Vec2::new(20., 10.),
+            Vec2::new(20., 20.),
+            Vec2::new(10., 20.),
+            Vec2::new(10., 15.),
+        ])]);
+        let path = navigation.find_path(Vec2::new(0., 12.), Vec2::new(30., 12.)).unwrap();
+        assert_eq!(
+            path,
+            vec![
+                Vec2::new(0., 12.),
+                Vec2::new(10., 10.),
+                Vec2::new(20., 10.),
+                Vec2::new(30., 12.)
+            ]
+        );
+    }
+
+    #[test]
+    fn transformed_navigation() {
+        let navigation = Navigation::new(vec![square(10., 10., 10.), square(30., 0., 10.)]);
+        let transform = Transform2::from_scale_rotation_translation(
+            Vec2::new(2., 2.),
+            std::f64::consts::PI / 3.,
+            Vec2::new(-5., 7.),
+        );
+        let transformed = navigation.transformed(&transform).unwrap();
+        let (start, end) = (Vec2::new(0., 15.), Vec2::new(50., 5.));
+        let path = navigation.find_path(start, end).unwrap();
+        let transformed_path = transformed
+            .find_path(transform.apply(start), transform.apply(end))
+            .unwrap();
+        assert_eq!(path.len(), transformed_path.len());
+        for (point, transformed_point) in path.into_iter().zip(transformed_path) {
+            assert!(transform.apply(point).dist(transformed_point) < 1e-9);
+        }
+        let mirror = Transform2::scale_xy(-1., 1.);
+        assert!(navigation.transformed(&mirror).is_none());
+    }
+
+    #[test]
+    fn simplified_obstacle() {
+        let vertices = vec![
+            Vec2::new(10., 10.),
+            Vec2::new(15., 10.2),
+            Vec2::new(20., 10.),
+            Vec2::new(19.9, 15.),
+            Vec2::new(20., 20.),
+            Vec2::new(10., 20.),
+        ];
+        let obstacle = NavigationObstacle::new_simplified(vertices, 0.5);
+        assert_eq!(obstacle.shape, square(10., 10., 10.).shape);
+        let navigation = Navigation::new(vec![obstacle]);
+        assert_eq!(
+            navigation.find_path(Vec2::new(0., 12.), Vec2::new(30., 12.)).unwrap(),
+            vec![
+                Vec2::new(0., 12.),
+                Vec2::new(10., 10.),
+                Vec2::new(20., 10.),
+                Vec2::new(30., 12.)
+            ]
+        );
+    }
+
+    #[test]
+    fn polyline_obstacles() {
+        let wall = NavigationObstacle::new_polyline(vec![Vec2::new(10., -5.), Vec2::new(10., 20.)]);
+        let navigation = Navigation::new(vec![wall]);
+        assert!(!navigation.has_line_of_sight(Vec2::new(0., 10.), Vec2::new(20., 10.)));
+        assert_eq!(
+            navigation.find_path(Vec2::new(0., 10.), Vec2::new(20., 10.)).unwrap(),
+            vec![Vec2::new(0., 10.), Vec2::new(10., 20.), Vec2::new(20., 10.)]
+        );
+        // Around a bend, and out of it again
+        let fence = NavigationObstacle::new_polyline(vec![
+            Vec2::new(0., 0.),
+            Vec2::new(20., 0.),
+            Vec2::new(20., 20.),
+            Vec2::new(30., 20.),
+        ]);
+        let navigation = Navigation::new(vec![fence]);
+        assert_eq!(
+            navigation.find_path(Vec2::new(10., 10.), Vec2::new(30., -5.)).unwrap(),
+            vec![Vec2::new(10., 10.), Vec2::new(0., 0.), Vec2::new(30., -5.)]
+        );
+        assert_eq!(
+            navigation.find_path(Vec2::new(10., 10.), Vec2::new(25., 25.)).unwrap(),
+            vec![Vec2::new(10., 10.), Vec2::new(20., 20.), Vec2::new(25., 25.)]
+        );
+        // Over the top of an arch, along its edge
+        let arch = NavigationObstacle::new_polyline(vec![
+            Vec2::new(0., 0.),
+            Vec2::new(0., 10.),
+            Vec2::new(10., 10.),
+            Vec2::new(10., 0.),
+        ]);
+        let navigation = Navigation::new(vec![arch]);
+        assert_eq!(
+            navigation.find_path(Vec2::new(-5., 8.), Vec2::new(15., 8.)).unwrap(),
+            vec![
+                Vec2::new(-5., 8.),
+                Vec2::new(0., 10.),
+                Vec2::new(10., 10.),
+                Vec2::new(15., 8.)
+            ]
+        );
+        // Not through a Z, from one side of its middle edge to the other
+        let fence = NavigationObstacle::new_polyline(vec![
+            Vec2::new(10., 0.),
+            Vec2::new(0., 0.),
+            Vec2::new(0., 10.),
+            Vec2::new(-10., 10.),
+        ]);
+        let navigation = Navigation::new(vec![fence]);
+        let path = navigation.find_path(Vec2::new(-1., 1.), Vec2::new(1., 9.)).unwrap();
+        assert!((path_length(&path) - 24.1).abs() < 0.1, "{:?}", path);
+    }
+
+    #[test]
+    fn thick_polyline_obstacle() {
+        let vertices = vec![Vec2::new(10., -5.), Vec2::new(10., 20.)];
+        let wall = NavigationObstacle::new_thick_polyline(vertices.clone(), 4., PI / 8.);
+        let navigation = Navigation::new(vec![wall]);
+        let path = navigation.find_path(Vec2::new(0., 10.), Vec2::new(20., 10.)).unwrap();
+        assert!(path[1..path.len() - 1].iter().all(|point| point.y > 20.));
+        let clearances = Navigation::new(vec![NavigationObstacle::new_polyline(vertices)]).path_clearance(&path);
+        for clearance in clearances {
+            assert!(clearance.unwrap().distance > 1.9);
+        }
+    }
+
+    #[test]
+    fn expand_decomposed() {
+        let u_shape = vec![
+            Vec2::new(0., 0.),
+            Vec2::new(30., 0.),
+            Vec2::new(30., 30.),
+            Vec2::new(20., 30.),
+            Vec2::new(20., 10.),
+            Vec2::new(10., 10.),
+            Vec2::new(10., 30.),
+            Vec2::new(0., 30.),
+        ];
+        let expanded = NavigationObstacle::new(u_shape.clone()).expand_decomposed(2., PI / 8.);
+        assert_eq!(expanded.len(), 1);
+        let shape = &expanded[0].shape;
+        for (index0, segment0) in shape.segments().into_iter().enumerate() {
+            for segment1 in shape.segments().into_iter().skip(index0 + 1) {
+                assert!(!segment0.connective_intersect(&segment1));
+            }
+        }
+        let navigation = Navigation::new(expanded);
+        let path = navigation.find_path(Vec2::new(15., 40.), Vec2::new(15., -10.)).unwrap();
+        let clearances = Navigation::new(vec![NavigationObstacle::new(u_shape)]).path_clearance(&path);
+        for clearance in clearances {
+            assert!(clearance.unwrap().distance > 1.9);
+        }
+        // Polylines are expanded into capsules
+        let wall = NavigationObstacle::new_polyline(vec![Vec2::new(0., 0.), Vec2::new(10., 0.), Vec2::new(10., 10.)]);
+        let expanded = wall.expand_decomposed(1., PI / 4.);
+        assert_eq!(expanded.len(), 1);
+        assert!(!expanded[0].shape.contains(Vec2::new(5., 5.)));
+        assert!(expanded[0].shape.contains(Vec2::new(5., 0.5)));
+    }
+
+    #[test]
+    fn obstacle_with_hole() {
+        let polygon = Polygon::new(square(0., 0., 30.).shape, vec![square(10., 10., 10.).shape]);
+        let navigation = Navigation::new(vec![NavigationObstacle::from_polygon(&polygon)]);
+        let inside = Vec2::new(12., 12.);
+        assert_eq!(navigation.find_path(Vec2::new(-10., 15.), inside), None);
+        assert_eq!(
+            navigation.find_path(inside, Vec2::new(18., 15.)),
+            Some(vec![inside, Vec2::new(18., 15.)])
+        );
+        assert_eq!(
+            navigation.find_path(Vec2::new(-10., 15.), Vec2::new(40., 15.)),
+            Some(vec![
+                Vec2::new(-10., 15.),
+                Vec2::new(0., 0.),
+                Vec2::new(30., 0.),
+                Vec2::new(40., 15.)
+            ])
+        );
+    }
+
+    #[test]
+    fn path_search_steps_to_same_path() {
+        let navigation = Navigation::new(vec![square(10., 10., 10.), square(30., 0., 10.)]);
+        let (start, end) = (Vec2::new(0., 15.), Vec2::new(50., 5.));
+        let mut search = navigation.start_search(start, end);
+        let mut steps = 0;
+        let path = loop {
+            steps += 1;
+            match search.step(AStarBudget::Iterations(1)) {
+                PathSearchStatus::Pending => continue,
+                PathSearchStatus::Found(path) => break path.points,
+                PathSearchStatus::Failed(failure) => panic!("path not found: {:?}", failure),
+            }
+        };
+        assert!(steps > 1);
+        assert_eq!(Some(path), navigation.find_path(start, end));
+    }
+
+    #[test]
+    fn path_search_cancel() {
+        let navigation = Navigation::new(vec![square(10., 10., 10.)]);
+        let mut search = navigation.start_search(Vec2::new(0., 12.), Vec2::new(30., 12.));
+        assert_eq!(search.step(AStarBudget::Iterations(1)), PathSearchStatus::Pending);
+        search.cancel();
+        assert_eq!(
+            search.step(AStarBudget::Unlimited),
+            PathSearchStatus::Failed(AStarFailure::Cancelled)
+        );
+    }
+
+    #[test]
+    fn find_path_limits() {
+        let navigation = Navigation::new(vec![square(10., 10., 10.)]);
+        let (start, end) = (Vec2::new(0., 12.), Vec2::new(30., 12.));
+        let options = FindPathOptions {
+            max_cost: Some(31.),
+            ..FindPathOptions::default()
+        };
+        assert!(navigation.find_path_with_options(start, end, &options).is_ok());
+        let options = FindPathOptions {
+            max_cost: Some(30.3),
+            ..FindPathOptions::default()
+        };
+        assert_eq!(
+            navigation.find_path_with_options(start, end, &options),
+            Err(AStarFailure::CostLimitReached)
+        );
+        let options = FindPathOptions {
+            max_cost: Some(5.),
+            ..FindPathOptions::default()
+        };
+        assert_eq!(
+            navigation.find_path_with_options(start, Vec2::new(0., 20.), &options),
+            Err(AStarFailure::CostLimitReached)
+        );
+        let options = FindPathOptions {
+            max_expansions: Some(2),
+            ..FindPathOptions::default()
+        };
+        assert_eq!(
+            navigation.find_path_with_options(start, end, &options),
+            Err(AStarFailure::ExpansionLimitReached)
+        );
+    }
+
+    fn assert_partial_path(path: Result<NavigationPath, AStarFailure>, expected: &[Vec2]) {
+        let path = path.unwrap();
+        assert!(path.partial);
+        assert_eq!(path.points.len(), expected.len());
+        for (point, expected_point) in path.points.iter().zip(expected) {
+            assert!(
+                point.dist(*expected_point) < 1e-6,
+                "{:?} != {:?}",
+                path.points,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn find_path_closest_if_unreachable() {
+        let navigation = Navigation::new(vec![square(10., 10., 10.)]);
+        let options = FindPathOptions {
+            closest_if_unreachable: true,
+            ..FindPathOptions::default()
+        };
+        assert_partial_path(
+            navigation.find_path_with_options(Vec2::new(0., 0.), Vec2::new(15., 12.), &options),
+            &[Vec2::new(0., 0.), Vec2::new(15., 10.)],
+        );
+        assert_partial_path(
+            navigation.find_path_with_options(Vec2::new(0., 30.), Vec2::new(12., 15.), &options),
+            &[Vec2::new(0., 30.), Vec2::new(10., 15.)],
+        );
+        assert_partial_path(
+            navigation.find_path_with_options(Vec2::new(15., 0.), Vec2::new(12., 15.), &options),
+            &[Vec2::new(15., 0.), Vec2::new(10., 10.), Vec2::new(10., 15.)],
+        );
+        let options = FindPathOptions {
+            max_cost: Some(10.),
+            closest_if_unreachable: true,
+            ..FindPathOptions::default()
+        };
+        assert_partial_path(
+            navigation.find_path_with_options(Vec2::new(0., 0.), Vec2::new(0., 30.), &options),
+            &[Vec2::new(0., 0.), Vec2::new(0., 10.)],
+        );
+
+        // The corner of the square is closest to the end, but it has only been reached over the cost limit
+        let navigation = Navigation::new(vec![
+            NavigationObstacle::new(vec![
+                Vec2::new(5., 1.),
+                Vec2::new(25., 1.),
+                Vec2::new(25., 3.),
+                Vec2::new(5., 3.),
+            ]),
+            square(10., 10., 10.),
+        ]);
+        let options = FindPathOptions {
+            max_cost: Some(20.),
+            closest_if_unreachable: true,
+            ..FindPathOptions::default()
+        };
+        let path = navigation
+            .find_path_with_options(Vec2::new(0., 2.), Vec2::new(20., 2.), &options)
+            .unwrap();
+        assert!(path.partial);
+        assert!(path_length(&path.points) <= 20.);
+    }
+
+    #[test]
+    fn sight_lines() {
+        let mut random = Random(3);
+        let navigation = Navigation::new(scattered_polygons(&mut random, 20, 200.));
+        let vertices: Vec<Vec2> = navigation
+            .obstacles
+            .iter()
+            .flat_map(|obstacle| obstacle.shape.vertices.iter().copied())
+            .collect();
+        for _ in 0..20 {
+            let viewpoint = random.point(200.);
+            let sight_lines = SightLines::new(viewpoint, &navigation.obstacles);
+            for point in (0..50).map(|_| random.point(200.)).chain(vertices.iter().copied()) {
+                assert_eq!(
+                    sight_lines.can_see(point),
+                    navigation.has_line_of_sight(point, viewpoint)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_free_point() {
+        let navigation = Navigation::new(vec![square(10., 10., 10.), square(18., 10., 10.)]);
+        let assert_close = |point: Vec2, expected: Vec2| {
+            let free_point = navigation.nearest_free_point(point);
+            assert!(free_point.dist(expected) < 1e-6, "{:?} != {:?}", free_point, expected);
+            assert!(!navigation.is_inside_obstacle(free_point));
+        };
+        assert_eq!(navigation.nearest_free_point(Vec2::new(5., 5.)), Vec2::new(5., 5.));
+        assert_close(Vec2::new(12., 15.), Vec2::new(10., 15.));
+        assert_close(Vec2::new(15., 11.), Vec2::new(15., 10.));
+        assert_close(Vec2::new(19., 12.), Vec2::new(19., 10.));
+        assert_close(Vec2::new(11., 12.), Vec2::new(10., 12.));
+    }
+
+    #[test]
+    fn find_path_snap_endpoints() {
+        let navigation = Navigation::new(vec![square(10., 10., 10.)]);
+        let options = FindPathOptions {
+            snap_endpoints: true,
+            ..FindPathOptions::default()
+        };
+        let path = navigation
+            .find_path_with_options(Vec2::new(0., 15.), Vec2::new(19., 15.), &options)
+            .unwrap();
+        assert!(!path.partial);
+        assert_eq!(path.points.len(), 4);
+        assert!(path.points[3].dist(Vec2::new(20., 15.)) < 1e-6);
+    }
+
+    #[test]
+    fn nearest_free_point_between_overlapping_obstacles() {
+        // The closest point of every edge is covered by the other obstacle
+        let navigation = Navigation::new(vec![
+            NavigationObstacle::new(vec![
+                Vec2::new(0., 0.),
+                Vec2::new(10., 0.),
+                Vec2::new(10., 2.),
+                Vec2::new(0., 2.),
+            ]),
+            NavigationObstacle::new(vec![
+                Vec2::new(4., -10.),
+                Vec2::new(6., -10.),
+                Vec2::new(6., 10.),
+                Vec2::new(4., 10.),
+            ]),
+        ]);
+        let free_point = navigation.nearest_free_point(Vec2::new(5., 1.));
+        assert!(!navigation.is_inside_obstacle(free_point));
+        assert!(
+            (free_point.dist(Vec2::new(5., 1.)) - 2f64.sqrt()).abs() < 1e-6,
+            "{:?}",
+            free_point
+        );
+    }
+
+    #[test]
+    fn line_of_sight() {
+        let navigation = Navigation::new(vec![square(10., 10., 10.)]);
+        assert!(navigation.has_line_of_sight(Vec2::new(0., 0.), Vec2::new(30., 0.)));
+        assert!(navigation.has_line_of_sight(Vec2::new(0., 0.), Vec2::new(10., 10.)));
+        assert!(!navigation.has_line_of_sight(Vec2::new(0., 15.), Vec2::new(30., 15.)));
+    }
+
+    #[test]
+    fn raycast() {
+        let navigation = Navigation::new(vec![square(10., 10., 10.), square(30., 10., 10.)]);
+        let hit = navigation.raycast(Vec2::new(0., 15.), Vec2::new(2., 0.), 100.).unwrap();
+        assert_eq!(hit.point, Vec2::new(10., 15.));
+        assert_eq!(hit.distance, 10.);
+        assert_eq!(hit.obstacle_index, 0);
+        assert_eq!(navigation.obstacles[0].shape.vertices[hit.edge_index].x, 10.);
+        assert_eq!(hit.normal, Vec2::new(-1., 0.));
+        let hit = navigation
+            .raycast(Vec2::new(50., 15.), Vec2::new(-1., 0.), 100.)
+            .unwrap();
+        assert_eq!(hit.point, Vec2::new(40., 15.));
+        assert_eq!(hit.obstacle_index, 1);
+        assert_eq!(hit.normal, Vec2::new(1., 0.));
+        assert_eq!(navigation.raycast(Vec2::new(0., 15.), Vec2::new(1., 0.), 5.), None);
+        assert_eq!(navigation.raycast(Vec2::new(0., 15.), Vec2::new(-1., 0.), 100.), None);
+    }
+
+    #[test]
+    fn clearance() {
+        let navigation = Navigation::new(vec![square(10., 10., 10.)]);
+        let clearance = navigation.clearance_at(Vec2::new(15., 5.)).unwrap();
+        assert_eq!(clearance.distance, 5.);
+        assert_eq!(clearance.point, Vec2::new(15., 10.));
+        assert_eq!(clearance.obstacle_index, 0);
+        assert_eq!(Navigation::new(vec![]).clearance_at(Vec2::new(15., 5.)), None);
+
+        let path = [Vec2::new(0., 0.), Vec2::new(30., 0.), Vec2::new(30., 15.)];
+        let distances: Vec<f64> = navigation
+            .path_clearance(&path)
+            .into_iter()
+            .map(|clearance| clearance.unwrap().distance)
+            .collect();
+        assert_eq!(distances, vec![10., 10.]);
+    }
+
+    /// A frame split by a wall that only ends on the far right, with many small obstacles on both
+    /// sides. The straight-line distance leads the search into the obstacles on the near side.
+    fn detour_map() -> Vec<NavigationObstacle> {
+        let frame = Polygon::new(
+            square(-10., -10., 220.).shape().clone(),
+            vec![square(0., 0., 200.).shape().clone()],
+        );
+        let mut obstacles = vec![
+            NavigationObstacle::from_polygon(&frame),
+            NavigationObstacle::new(vec![
+                Vec2::new(-5., 99.),
+                Vec2::new(190., 99.),
+                Vec2::new(190., 101.),
+                Vec2::new(-5., 101.),
+            ]),
+        ];
+        for i in 0..8 {
+            for j in 0..4 {
+                obstacles.push(square(5. + i as f64 * 12., 50. + j as f64 * 12., 3.));
+                obstacles.push(square(5. + i as f64 * 12., 140. + j as f64 * 12., 3.));
+            }
+        }
+        obstacles
+    }
+
+    #[test]
+    fn landmarks() {
+        let mut navigation = Navigation::new(detour_map());
+        let (start, end) = (Vec2::new(20., 90.), Vec2::new(20., 110.));
+        let path = navigation.find_path(start, end).unwrap();
+        let options = FindPathOptions {
+            max_expansions: Some(60),
+            ..FindPathOptions::default()
+        };
+        assert_eq!(
+            navigation.find_path_with_options(start, end, &options),
+            Err(AStarFailure::ExpansionLimitReached)
+        );
+        navigation.precompute_landmarks(4);
+        assert_eq!(navigation.landmark_count(), 4);
+        // The same path, with far fewer nodes expanded
+        assert_eq!(
+            navigation.find_path_with_options(start, end, &options).unwrap().points,
+            path
+        );
+        for &(start, end) in &[
+            (Vec2::new(150., 50.), Vec2::new(30., 150.)),
+            (Vec2::new(20., 20.), Vec2::new(180., 180.)),
+        ] {
+            let with_landmarks = navigation.find_path(start, end);
+            navigation.clear_landmarks();
+            assert_eq!(with_landmarks, navigation.find_path(start, end));
+            navigation.precompute_landmarks(4);
+        }
+        assert_eq!(navigation.find_path(start, Vec2::new(50., 230.)), None);
+
+        navigation.add_obstacle(square(120., 120., 20.));
+        assert_eq!(navigation.landmark_count(), 0);
+        navigation.precompute_landmarks(4);
+        navigation.remove_obstacle(0);
+        assert_eq!(navigation.landmark_count(), 0);
+    }
+
+    #[test]
+    fn added_obstacles_same_paths_as_fresh_build() {
+        let mut random = Random(7);
+        for _ in 0..4 {
+            let obstacles = scattered_polygons(&mut random, 14, 200.);
+            let mut navigation = Navigation::new(obstacles[..10].to_vec());
+            navigation.precompute_landmarks(4);
+            for obstacle in &obstacles[10..] {
+                navigation.add_obstacle(obstacle.clone());
+            }
+            let fresh = Navigation::new(obstacles);
+            for _ in 0..100 {
+                let (start, end) = (random.point(200.), random.point(200.));
+                match (navigation.find_path(start, end), fresh.find_path(start, end)) {
+                    (Some(path), Some(fresh_path)) => {
+                        assert!((path_length(&path) - path_length(&fresh_path)).abs() < 1e-9)
+                    }
+                    (path, fresh_path) => assert_eq!(path, fresh_path),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn taut_successors() {
+        let mut obstacles = detour_map();
+        obstacles.push(NavigationObstacle::new_polyline(vec![
+            Vec2::new(120., 20.),
+            Vec2::new(150., 60.),
+            Vec2::new(130., 80.),
+        ]));
+        obstacles.push(NavigationObstacle::new(vec![
+            Vec2::new(150., 130.),
+            Vec2::new(180., 150.),
+            Vec2::new(160., 155.),
+        ]));
+        let navigation = Navigation::new(obstacles);
+        let options = FindPathOptions {
+            taut_successors: true,
+            ..FindPathOptions::default()
+        };
+        let points: Vec<Vec2> = (0..5)
+            .flat_map(|i| (0..5).map(move |j| Vec2::new(4. + i as f64 * 47., 2. + j as f64 * 49.)))
+            .collect();
+        for &start in &points {
+            for &end in &points {
+                let path = navigation.find_path(start, end);
+                let taut_path = navigation.find_path_with_options(start, end, &options).ok();
+                assert_eq!(path.is_some(), taut_path.is_some());
+                if let (Some(path), Some(taut_path)) = (path, taut_path) {
+                    assert!((path_length(&path) - path_length(&taut_path.points)).abs() < 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn path_tree() {
+        let navigation = Navigation::new(detour_map());
+        let root = Vec2::new(20., 90.);
+        let mut path_tree = navigation.path_tree(root);
+        // An end point moving away and back again
+        for i in (0..10).chain((0..10).rev()) {
+            let end = Vec2::new(10. + i as f64 * 19., 104. + i as f64 * 9.);
+            let path = path_tree.path_to(&navigation, end).unwrap();
+            assert_eq!((path[0], *path.last().unwrap()), (root, end));
+            assert!((path_length(&path) - path_length(&navigation.find_path(root, end).unwrap())).abs() < 1e-9);
+            let mut reversed = path_tree.path_from(&navigation, end).unwrap();
+            reversed.reverse();
+            assert_eq!(reversed, path);
+        }
+        assert_eq!(path_tree.path_to(&navigation, Vec2::new(50., 230.)), None);
+    }
+
+    #[test]
+    fn path_cache() {
+        let obstacles = || {
+            vec![
+                square(0., -2., 10.),
+                NavigationObstacle::new_polyline(vec![Vec2::new(-6., 7.), Vec2::new(-6., 20.)]),
+            ]
+        };
+        let uncached = Navigation::new(obstacles());
+        let mut navigation = Navigation::new(obstacles());
+        navigation.enable_path_cache(8, 4.);
+        let end = Vec2::new(15., 5.);
+        assert_eq!(
+            navigation.find_path(Vec2::new(-5., 5.), end),
+            Some(vec![Vec2::new(-5., 5.), Vec2::new(0., 8.), Vec2::new(10., 8.), end])
+        );
+        assert_eq!(navigation.cached_path_count(), 1);
+        // From and to the same cells
+        let (start, nearby_end) = (Vec2::new(-6., 6.), Vec2::new(14., 6.));
+        assert_eq!(
+            navigation.find_path(start, nearby_end),
+            uncached.find_path(start, nearby_end)
+        );
+        assert_eq!(navigation.cached_path_count(), 1);
+        // The polyline is in the way of the cached path
+        let start = Vec2::new(-7., 7.5);
+        assert_eq!(navigation.find_path(start, end), uncached.find_path(start, end));
+        assert_ne!(navigation.find_path(start, end).unwrap()[1], Vec2::new(0., 8.));
+        assert_eq!(navigation.cached_path_count(), 1);
+
+        navigation.update_obstacles(vec![square(0., 2., 10.)]);
+        assert_eq!(navigation.cached_path_count(), 0);
+        assert_eq!(
+            navigation.find_path(Vec2::new(-5., 5.), end),
+            Some(vec![Vec2::new(-5., 5.), Vec2::new(0., 2.), Vec2::new(10., 2.), end])
+        );
+    }
+
+    #[test]
+    fn add_and_remove_obstacles() {
+        let edges = |navigation: &Navigation| {
+            let mut edges: Vec<[f64; 4]> = navigation
+                .internal_navigation_graph()
+                .into_iter()
+                .map(|segment| [segment.p0.x, segment.p0.y, segment.p1.x, segment.p1.y])
+                .collect();
+            edges.sort_by(|edge0, edge1| edge0.partial_cmp(edge1).unwrap());
+            edges
+        };
+        let mut obstacles = detour_map();
+        let mut navigation = Navigation::new(obstacles.clone());
+        let crate_obstacle = square(100., 60., 20.);
+        let changed_edges = navigation.add_obstacle(crate_obstacle.clone());
+        obstacles.push(crate_obstacle);
+        assert!(!changed_edges.is_empty());
+        let rebuilt = Navigation::new(obstacles.clone());
+        assert_eq!(edges(&navigation), edges(&rebuilt));
+        let (start, end) = (Vec2::new(90., 70.), Vec2::new(130., 70.));
+        assert_eq!(navigation.find_path(start, end), rebuilt.find_path(start, end));
+
+        // The wall
+        navigation.remove_obstacle(1);
+        obstacles.remove(1);
+        let rebuilt = Navigation::new(obstacles.clone());
+        assert_eq!(edges(&navigation), edges(&rebuilt));
+        let end = Vec2::new(20., 170.);
+        assert_eq!(navigation.find_path(start, end), rebuilt.find_path(start, end));
+        navigation.remove_obstacle(obstacles.len() - 1);
+        obstacles.pop();
+        assert_eq!(edges(&navigation), edges(&Navigation::new(obstacles)));
+    }
+
+    #[test]
+    fn dynamic_path() {
+        let mut navigation = Navigation::new(detour_map());
+        let (start, goal) = (Vec2::new(20., 30.), Vec2::new(20., 170.));
+        let mut dynamic_path = navigation.dynamic_path(start, goal);
+        let assert_same_path = |path: Option<Vec<Vec2>>, navigation: &Navigation, agent: Vec2| {
+            let expected = navigation.find_path(agent, goal);
+            assert_eq!(path.is_some(), expected.is_some());
+            if let (Some(path), Some(expected)) = (path, expected) {
+                assert_eq!((path[0], *path.last().unwrap()), (agent, goal));
+                assert!((path_length(&path) - path_length(&expected)).abs() < 1e-9);
+            }
+        };
+        assert_same_path(dynamic_path.path(&navigation), &navigation, start);
+
+        // Walking towards the gap at the end of the wall
+        let mut agent = start;
+        for _ in 0..4 {
+            let path = dynamic_path.path(&navigation).unwrap();
+            agent = agent + (path[1] - agent) * 0.3;
+            assert_same_path(dynamic_path.update(&navigation, agent, &[]), &navigation, agent);
+        }
+
+        // A crate falls on the way, and the gap gets closed by a door
+        let changed_edges = navigation.add_obstacle(square(150., 60., 25.));
+        assert_same_path(
+            dynamic_path.update(&navigation, agent, &changed_edges),
+            &navigation,
+            agent,
+        );
+        let changed_edges = navigation.add_obstacle(square(185., 95., 20.));
+        assert_eq!(dynamic_path.update(&navigation, agent, &changed_edges), None);
+        let door = navigation.obstacles.len() - 1;
+        let changed_edges = navigation.remove_obstacle(door);
+        assert_same_path(
+            dynamic_path.update(&navigation, agent, &changed_edges),
+            &navigation,
+            agent,
+        );
+        let changed_edges = navigation.remove_obstacle(door - 1);
+        agent = agent + Vec2::new(5., 5.);
+        assert_same_path(
+            dynamic_path.update(&navigation, agent, &changed_edges),
+            &navigation,
+            agent,
+        );
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn serialize_with_landmarks() {
+        let mut navigation = Navigation::new(detour_map());
+        navigation.precompute_landmarks(3);
+        let json = serde_json::to_string(&navigation).unwrap();
+        let deserialized: Navigation = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.landmark_count(), 3);
+        let (start, end) = (Vec2::new(20., 90.), Vec2::new(20., 110.));
+        assert_eq!(deserialized.find_path(start, end), navigation.find_path(start, end));
+    }
+}
@@ -0,0 +1,773 @@
+//! A navigation mesh backend. The free space is triangulated with a constrained Delaunay
+//! triangulation whose constrained edges are the obstacle edges, the path is searched over the
+//! triangles and then pulled taut with the funnel algorithm.
+//!
+//! Compared to the visibility graph of `Navigation`, the mesh only grows linearly with the number
+//! of obstacle vertices, but the found paths are not always the shortest, since the corridor of
+//! triangles is searched with a single point on each crossed edge, the one closest to the straight
+//! line between the start and end point.
+//!
+//! Building the mesh inserts the obstacle vertices one after another, each located by walking from
+//! the triangle added last, and then walks along every obstacle edge through the triangles it
+//! crosses. Since consecutive vertices of an outline are close to each other, the walks are short
+//! and building takes close to linear time in the number of vertices, though quadratic time in the
+//! worst case.
+
+use std::collections::{HashMap, HashSet};
+
+use noisy_float::prelude::*;
+use noisy_float::types::N64;
+
+use crate::a_star::{a_star, AStarInput};
+use crate::geometry::{incircle, orient2d, point_key, Aabb, PointKey, Segment, Shape, Sign, Vec2};
+use crate::navigation::{NavigationObstacle, PathFinder};
+
+fn undirected(u: usize, v: usize) -> (usize, usize) {
+    (u.min(v), u.max(v))
+}
+
+/// A triangulation under construction. Triangles are stored counterclockwise in a y-up coordinate
+/// system, i.e. in `ShapeWindingOrder::Clockwise` order.
+struct Triangulation {
+    vertices: Vec<Vec2>,
+    vertex_indices: HashMap<PointKey, usize>,
+    triangles: Vec<Option<[usize; 3]>>,
+    /// The triangle added last at every vertex, from which the others can be found with `edges`.
+    vertex_triangles: Vec<usize>,
+    /// The triangle on the left of every directed edge.
+    edges: HashMap<(usize, usize), usize>,
+    constrained: HashSet<(usize, usize)>,
+}
+
+/// Where walking along a new constrained edge from its first vertex ends, see
+/// `Triangulation::walk_constraint`.
+enum ConstraintWalk {
+    /// At its last vertex, after crossing these edges, each given by its vertex on the right and
+    /// its vertex on the left of the constrained edge.
+    End(Vec<(usize, usize)>),
+    /// At a vertex on the constrained edge.
+    Vertex(usize),
+    /// At the crossing with another constrained edge.
+    Constraint(Vec2),
+}
+
+impl Triangulation {
+    /// Starts with the two triangles of `bounds`, so every inserted point has to lie within it.
+    fn new(bounds: Aabb) -> Self {
+        let mut triangulation = Triangulation {
+            vertices: bounds.vertices().to_vec(),
+            vertex_indices: HashMap::new(),
+            triangles: vec![],
+            vertex_triangles: vec![0; 4],
+            edges: HashMap::new(),
+            constrained: HashSet::new(),
+        };
+        for (index, &vertex) in bounds.vertices().iter().enumerate() {
+            triangulation.vertex_indices.insert(point_key(vertex), index);
+            triangulation.constrained.insert(undirected(index, (index + 1) % 4));
+        }
+        triangulation.add_triangle([0, 1, 2]);
+        triangulation.add_triangle([0, 2, 3]);
+        triangulation
+    }
+    fn add_triangle(&mut self, triangle: [usize; 3]) -> usize {
+        let triangle_index = self.triangles.len();
+        for corner in 0..3 {
+            self.edges
+                .insert((triangle[corner], triangle[(corner + 1) % 3]), triangle_index);
+            self.vertex_triangles[triangle[corner]] = triangle_index;
+        }
+        self.triangles.push(Some(triangle));
+        triangle_index
+    }
+    fn remove_triangle(&mut self, triangle_index: usize) -> [usize; 3] {
+        let triangle = self.triangles[triangle_index].take().unwrap();
+        for corner in 0..3 {
+            self.edges.remove(&(triangle[corner], triangle[(corner + 1) % 3]));
+        }
+        triangle
+    }
+    /// The corner of the triangle on the left of the directed edge `(u, v)` that is not on it.
+    fn opposite(&self, u: usize, v: usize) -> Option<usize> {
+        let triangle = self.triangles[*self.edges.get(&(u, v))?].unwrap();
+        triangle.iter().copied().find(|&corner| corner != u && corner != v)
+    }
+    /// The side of every edge of `triangle` that `point` is on, edge `i` going from corner `i` to
+    /// corner `i + 1`.
+    fn sides(&self, triangle: [usize; 3], point: Vec2) -> [Sign; 3] {
+        let side = |corner: usize| {
+            orient2d(
+                self.vertices[triangle[corner]],
+                self.vertices[triangle[(corner + 1) % 3]],
+                point,
+            )
+        };
+        [side(0), side(1), side(2)]
+    }
+    /// The triangle containing `point`, and the edge of it that `point` is on, if any. Walks towards
+    /// `point` from the triangle added last, which is usually close, since consecutive vertices of
+    /// an obstacle are inserted one after another.
+    fn locate(&self, point: Vec2) -> (usize, Option<usize>) {
+        let mut triangle_index = self.triangles.iter().rposition(Option::is_some).unwrap();
+        // A walk can go around in circles once edges are constrained, then every triangle is tried
+        for _ in 0..self.triangles.len() {
+            let triangle = self.triangles[triangle_index].unwrap();
+            let sides = self.sides(triangle, point);
+            match sides.iter().position(|&side| side == Sign::Negative) {
+                Some(corner) => match self.edges.get(&(triangle[(corner + 1) % 3], triangle[corner])) {
+                    Some(&neighbor) => triangle_index = neighbor,
+                    None => break,
+                },
+                None => return (triangle_index, sides.iter().position(|&side| side == Sign::Zero)),
+            }
+        }
+        self.triangles
+            .iter()
+            .enumerate()
+            .find_map(|(triangle_index, triangle)| {
+                let sides = self.sides((*triangle)?, point);
+                if sides.contains(&Sign::Negative) {
+                    None
+                } else {
+                    Some((triangle_index, sides.iter().position(|&side| side == Sign::Zero)))
+                }
+            })
+            .expect("points are inserted within the bounds")
+    }
+    /// The triangles that have `vertex` as a corner.
+    fn triangles_around(&self, vertex: usize) -> Vec<[usize; 3]> {
+        let corner_of = |triangle: [usize; 3]| triangle.iter().position(|&corner| corner == vertex).unwrap();
+        let first = self.triangles[self.vertex_triangles[vertex]].unwrap();
+        let mut triangles = vec![first];
+        // Counterclockwise around the vertex, and clockwise from the first triangle if that ends
+        // at the bounds
+        let mut triangle = first;
+        while let Some(&next) = self.edges.get(&(vertex, triangle[(corner_of(triangle) + 2) % 3])) {
+            triangle = self.triangles[next].unwrap();
+            if triangle == first {
+                return triangles;
+            }
+            triangles.push(triangle);
+        }
+        let mut triangle = first;
+        while let Some(&prev) = self.edges.get(&(triangle[(corner_of(triangle) + 1) % 3], vertex)) {
+            triangle = self.triangles[prev].unwrap();
+            triangles.push(triangle);
+        }
+        triangles
+    }
+    fn insert_vertex(&mut self, point: Vec2) -> usize {
+        if let Some(&index) = self.vertex_indices.get(&point_key(point)) {
+            return index;
+        }
+        let index = self.vertices.len();
+        self.vertices.push(point);
+        self.vertex_indices.insert(point_key(point), index);
+        self.vertex_triangles.push(usize::MAX);
+        let (triangle_index, zero_edge) = self.locate(point);
+        let triangle = self.remove_triangle(triangle_index);
+        let mut outer_edges = vec![];
+        match zero_edge {
+            None => {
+                for corner in 0..3 {
+                    let (u, v) = (triangle[corner], triangle[(corner + 1) % 3]);
+                    self.add_triangle([u, v, index]);
+                    outer_edges.push((u, v));
+                }
+            }
+            Some(corner) => {
+                // On an edge, which is split along with the triangle on its other side
+                let (u, v, w) = (triangle[corner], triangle[(corner + 1) % 3], triangle[(corner + 2) % 3]);
+                self.add_triangle([u, index, w]);
+                self.add_triangle([index, v, w]);
+                outer_edges.extend_from_slice(&[(v, w), (w, u)]);
+                if let Some(&other_index) = self.edges.get(&(v, u)) {
+                    let x = self.opposite(v, u).unwrap();
+                    self.remove_triangle(other_index);
+                    self.add_triangle([v, index, x]);
+                    self.add_triangle([index, u, x]);
+                    outer_edges.extend_from_slice(&[(u, x), (x, v)]);
+                }
+                if self.constrained.remove(&undirected(u, v)) {
+                    self.constrained.insert(undirected(u, index));
+                    self.constrained.insert(undirected(index, v));
+                }
+            }
+        }
+        self.legalize(outer_edges);
+        index
+    }
+    /// Flips edges until none of `edges`, or the edges affected by flipping them, violates the
+    /// Delaunay condition. Constrained edges are never flipped.
+    fn legalize(&mut self, mut edges: Vec<(usize, usize)>) {
+        while let Some((u, v)) = edges.pop() {
+            if self.constrained.contains(&undirected(u, v)) {
+                continue;
+            }
+            let (w, x) = match (self.opposite(u, v), self.opposite(v, u)) {
+                (Some(w), Some(x)) => (w, x),
+                _ => continue,
+            };
+            let (pu, pv, pw, px) = (self.vertices[u], self.vertices[v], self.vertices[w], self.vertices[x]);
+            // The quadrilateral u, x, v, w has to be convex to be flipped
+            if incircle(pu, pv, pw, px) != Sign::Positive
+                || orient2d(pu, px, pw) != Sign::Positive
+                || orient2d(px, pv, pw) != Sign::Positive
+            {
+                continue;
+            }
+            self.remove_triangle(self.edges[&(u, v)]);
+            self.remove_triangle(self.edges[&(v, u)]);
+            self.add_triangle([u, x, w]);
+            self.add_triangle([x, v, w]);
+            edges.extend_from_slice(&[(u, x), (x, v), (v, w), (w, u)]);
+        }
+    }
+    /// Makes `(a, b)` an edge of the triangulation that is never flipped. Vertices on the edge and
+    /// crossings with other constrained edges split it.
+    fn insert_constraint(&mut self, a: usize, b: usize, depth: usize) {
+        if a == b {
+            return;
+        }
+        if self.edges.contains_key(&(a, b)) || self.edges.contains_key(&(b, a)) {
+            self.constrained.insert(undirected(a, b));
+            return;
+        }
+        let crossed_edges = match self.walk_constraint(a, b) {
+            ConstraintWalk::End(crossed_edges) => crossed_edges,
+            ConstraintWalk::Vertex(index) => {
+                self.insert_constraint(a, index, depth);
+                self.insert_constraint(index, b, depth);
+                return;
+            }
+            ConstraintWalk::Constraint(point) => {
+                // Overlapping obstacles, split both edges at their crossing. Rounding may move the
+                // crossing slightly off the edges, which could repeat forever.
+                if depth < 8 {
+                    let index = self.insert_vertex(point);
+                    if index != a && index != b {
+                        self.insert_constraint(a, index, depth + 1);
+                        self.insert_constraint(index, b, depth + 1);
+                    }
+                }
+                return;
+            }
+        };
+        let crossed_triangles: HashSet<usize> = crossed_edges
+            .iter()
+            .flat_map(|&(u, v)| vec![self.edges[&(u, v)], self.edges[&(v, u)]])
+            .collect();
+        let mut cavity_edges: HashMap<usize, usize> = HashMap::new();
+        for &triangle_index in &crossed_triangles {
+            let triangle = self.remove_triangle(triangle_index);
+            for corner in 0..3 {
+                cavity_edges.insert(triangle[corner], triangle[(corner + 1) % 3]);
+            }
+        }
+        // Edges between removed triangles are in the map in both directions
+        let boundary: HashMap<usize, usize> = cavity_edges
+            .iter()
+            .filter(|&(&u, &v)| cavity_edges.get(&v) != Some(&u))
+            .map(|(&u, &v)| (u, v))
+            .collect();
+        // The boundary is counterclockwise, so the chain from `a` to `b` and the chain from `b` to
+        // `a`, each closed by the new edge, are counterclockwise as well
+        let chain = |from: usize, to: usize| {
+            let mut chain = vec![from];
+            while *chain.last().unwrap() != to && chain.len() <= boundary.len() {
+                chain.push(boundary[chain.last().unwrap()]);
+            }
+            chain
+        };
+        let mut new_edges = vec![];
+        for cavity in [chain(a, b), chain(b, a)].iter() {
+            let shape = Shape::new(cavity.iter().map(|&index| self.vertices[index]).collect());
+            for [next, vertex, prev] in shape.triangulate() {
+                let triangle = [cavity[prev], cavity[vertex], cavity[next]];
+                self.add_triangle(triangle);
+                for corner in 0..3 {
+                    new_edges.push((triangle[corner], triangle[(corner + 1) % 3]));
+                }
+            }
+        }
+        self.constrained.insert(undirected(a, b));
+        self.legalize(new_edges);
+    }
+    /// Walks along the segment from `a` to `b` through the triangles it crosses, until it reaches
+    /// `b`, a vertex on the segment or a constrained edge, whichever comes first.
+    fn walk_constraint(&self, a: usize, b: usize) -> ConstraintWalk {
+        let (pa, pb) = (self.vertices[a], self.vertices[b]);
+        let segment = Segment::new(pa, pb);
+        let side = |index: usize| orient2d(pa, pb, self.vertices[index]);
+        // The triangle around `a` that the segment leaves through the edge opposite of `a`
+        let mut crossed_edge = None;
+        for triangle in self.triangles_around(a) {
+            let corner = triangle.iter().position(|&corner| corner == a).unwrap();
+            let (right, left) = (triangle[(corner + 1) % 3], triangle[(corner + 2) % 3]);
+            for &index in &[right, left] {
+                if side(index) == Sign::Zero && (self.vertices[index] - pa).dot(pb - pa) > 0. {
+                    return ConstraintWalk::Vertex(index);
+                }
+            }
+            if side(right) == Sign::Negative && side(left) == Sign::Positive {
+                crossed_edge = Some((right, left));
+                break;
+            }
+        }
+        let (mut right, mut left) = crossed_edge.expect("the segment leaves its first vertex through a triangle");
+        let mut crossed_edges = vec![];
+        loop {
+            if self.constrained.contains(&undirected(right, left)) {
+                if let Some(point) = segment.intersect(&Segment::new(self.vertices[right], self.vertices[left])) {
+                    return ConstraintWalk::Constraint(point);
+                }
+            }
+            crossed_edges.push((right, left));
+            let index = self.opposite(left, right).unwrap();
+            if index == b {
+                return ConstraintWalk::End(crossed_edges);
+            }
+            match side(index) {
+                Sign::Negative => right = index,
+                Sign::Positive => left = index,
+                Sign::Zero => return ConstraintWalk::Vertex(index),
+            }
+        }
+    }
+}
+
+/// A navigation mesh, see the module documentation.
+pub struct NavMesh {
+    vertices: Vec<Vec2>,
+    triangles: Vec<[usize; 3]>,
+    /// The triangle across each edge of a triangle, for edges that can be walked across. Edge `i`
+    /// goes from corner `i` to corner `i + 1`.
+    neighbors: Vec<[Option<usize>; 3]>,
+    /// For crossing edge `i` of triangle `t`, identified by `3 * t + i`, the edges that can be
+    /// crossed next, i.e. the other edges of the triangle on the other side. The A* search runs
+    /// over these.
+    edge_adjacency: Vec<Vec<usize>>,
+    walkable: Vec<bool>,
+}
+
+impl NavMesh {
+    /// Triangulates the free space within `bounds`, which is grown to include all obstacles.
+    /// Points outside of it can not be navigated to.
+    pub fn new(obstacles: &[NavigationObstacle], bounds: Aabb) -> Self {
+        let shapes: Vec<&Shape> = obstacles.iter().map(NavigationObstacle::shape).collect();
+        let (min, max) = shapes.iter().flat_map(|shape| shape.vertices.iter()).fold(
+            (bounds.min, bounds.max),
+            |(min, max), vertex| {
+                (
+                    Vec2::new(min.x.min(vertex.x), min.y.min(vertex.y)),
+                    Vec2::new(max.x.max(vertex.x), max.y.max(vertex.y)),
+                )
+            },
+        );
+        let mut triangulation = Triangulation::new(Aabb::new(min, max));
+        let vertex_indices: Vec<Vec<usize>> = shapes
+            .iter()
+            .map(|shape| {
+                shape
+                    .vertices
+                    .iter()
+                    .map(|&vertex| triangulation.insert_vertex(vertex))
+                    .collect()
+            })
+            .collect();
+        for indices in &vertex_indices {
+            for (index, &vertex_index) in indices.iter().enumerate() {
+                triangulation.insert_constraint(vertex_index, indices[(index + 1) % indices.len()], 0);
+            }
+        }
+
+        let mut triangle_indices = vec![usize::MAX; triangulation.triangles.len()];
+        let triangles: Vec<[usize; 3]> = triangulation.triangles.iter().flatten().copied().collect();
+        for (new_index, (old_index, _)) in triangulation
+            .triangles
+            .iter()
+            .enumerate()
+            .filter(|(_, triangle)| triangle.is_some())
+            .enumerate()
+        {
+            triangle_indices[old_index] = new_index;
+        }
+        let Triangulation {
+            vertices,
+            edges,
+            constrained,
+            ..
+        } = triangulation;
+        let walkable: Vec<bool> = triangles
+            .iter()
+            .map(|triangle| {
+                let centroid = (vertices[triangle[0]] + vertices[triangle[1]] + vertices[triangle[2]]) / 3.;
+                !shapes.iter().any(|shape| shape.contains(centroid))
+            })
+            .collect();
+        let neighbors: Vec<[Option<usize>; 3]> = triangles
+            .iter()
+            .enumerate()
+            .map(|(triangle_index, triangle)| {
+                let mut neighbors = [None; 3];
+                if walkable[triangle_index] {
+                    for (corner, neighbor) in neighbors.iter_mut().enumerate() {
+                        let (u, v) = (triangle[corner], triangle[(corner + 1) % 3]);
+                        if constrained.contains(&undirected(u, v)) {
+                            continue;
+                        }
+                        *neighbor = edges
+                            .get(&(v, u))
+                            .map(|&old_index| triangle_indices[old_index])
+                            .filter(|&neighbor| walkable[neighbor]);
+                    }
+                }
+                neighbors
+            })
+            .collect();
+        NavMesh {
+            vertices,
+            edge_adjacency: (0..triangles.len() * 3)
+                .map(|edge| match neighbors[edge / 3][edge % 3] {
+                    Some(neighbor) => (0..3)
+                        .filter(|&corner| matches!(neighbors[neighbor][corner], Some(back) if back != edge / 3))
+                        .map(|corner| neighbor * 3 + corner)
+                        .collect(),
+                    None => vec![],
+                })
+                .collect(),
+            triangles,
+            neighbors,
+            walkable,
+        }
+    }
+    /// The walkable triangle containing `point`, if any.
+    fn locate(&self, point: Vec2) -> Option<usize> {
+        (0..self.triangles.len()).find(|&triangle_index| {
+            self.walkable[triangle_index]
+                && (0..3).all(|corner| {
+                    let triangle = self.triangles[triangle_index];
+                    orient2d(
+                        self.vertices[triangle[corner]],
+                        self.vertices[triangle[(corner + 1) % 3]],
+                        point,
+                    ) != Sign::Negative
+                })
+        })
+    }
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+    /// The walkable triangles, in `ShapeWindingOrder::Clockwise` order.
+    pub fn walkable_triangles(&self) -> Vec<Shape> {
+        self.triangles
+            .iter()
+            .zip(&self.walkable)
+            .filter(|(_, &walkable)| walkable)
+            .map(|(triangle, _)| Shape::new(triangle.iter().map(|&index| self.vertices[index]).collect()))
+            .collect()
+    }
+    pub fn find_path(&self, start: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
+        let start_triangle = self.locate(start)?;
+        let end_triangle = self.locate(end)?;
+        if start_triangle == end_triangle {
+            return Some(vec![start, end]);
+        }
+        let triangles_count = self.triangles.len();
+        // The edges leading into the end triangle can also continue to the end point
+        let end_edges: Vec<(usize, Vec<usize>)> = self.neighbors[end_triangle]
+            .iter()
+            .flatten()
+            .map(|&neighbor| {
+                let corner = (0..3)
+                    .find(|&corner| self.neighbors[neighbor][corner] == Some(end_triangle))
+                    .unwrap();
+                let edge = neighbor * 3 + corner;
+                let mut next_edges = self.edge_adjacency[edge].clone();
+                next_edges.push(triangles_count * 3 + 1);
+                (edge, next_edges)
+            })
+            .collect();
+        let input = NavMeshAStarInput {
+            navmesh: self,
+            start,
+            start_edges: (0..3)
+                .filter(|&corner| self.neighbors[start_triangle][corner].is_some())
+                .map(|corner| start_triangle * 3 + corner)
+                .collect(),
+            end,
+            end_edges,
+        };
+        let corridor = a_star(&input)?;
+        // The edges crossed along the corridor, as seen when walking along it
+        let mut portals = vec![(start, start)];
+        for &edge in &corridor[1..corridor.len() - 1] {
+            let triangle = self.triangles[edge / 3];
+            let (right, left) = (triangle[edge % 3], triangle[(edge % 3 + 1) % 3]);
+            portals.push((self.vertices[left], self.vertices[right]));
+        }
+        portals.push((end, end));
+        Some(string_pull(&portals))
+    }
+}
+
+/// The simple stupid funnel algorithm. `portals` are `(left, right)` pairs as seen when walking
+/// from the first to the last one, where the first and the last portal are the start and end point.
+fn string_pull(portals: &[(Vec2, Vec2)]) -> Vec<Vec2> {
+    let (start, _) = portals[0];
+    let (end, _) = portals[portals.len() - 1];
+    let mut path = vec![start];
+    let (mut apex, mut left, mut right) = (start, start, start);
+    let (mut left_index, mut right_index) = (0, 0);
+    let mut index = 1;
+    while index < portals.len() {
+        let (portal_left, portal_right) = portals[index];
+        // Narrow the funnel from the right, unless that crosses over the left side, in which case
+        // the left side is a corner of the path
+        if orient2d(apex, right, portal_right) != Sign::Negative {
+            if apex == right || orient2d(apex, left, portal_right) == Sign::Negative {
+                right = portal_right;
+                right_index = index;
+            } else {
+                // Consecutive portals can share the apex
+                if path.last() != Some(&left) {
+                    path.push(left);
+                }
+                apex = left;
+                right = apex;
+                right_index = left_index;
+                index = left_index + 1;
+                continue;
+            }
+        }
+        if orient2d(apex, left, portal_left) != Sign::Positive {
+            if apex == left || orient2d(apex, right, portal_left) == Sign::Positive {
+                left = portal_left;
+                left_index = index;
+            } else {
+                if path.last() != Some(&right) {
+                    path.push(right);
+                }
+                apex = right;
+                left = apex;
+                left_index = right_index;
+                index = right_index + 1;
+                continue;
+            }
+        }
+        index += 1;
+    }
+    if path.last() != Some(&end) {
+        path.push(end);
+    }
+    path
+}
+
+struct NavMeshAStarInput<'a> {
+    navmesh: &'a NavMesh,
+    start: Vec2,
+    start_edges: Vec<usize>,
+    end: Vec2,
+    end_edges: Vec<(usize, Vec<usize>)>,
+}
+
+impl NavMeshAStarInput<'_> {
+    fn get_position(&self, node: usize) -> Vec2 {
+        if node == self.start() {
+            self.start
+        } else if node == self.end() {
+            self.end
+        } else {
+            // The point of the edge closest to the straight line, which is fixed during the search
+            let triangle = self.navmesh.triangles[node / 3];
+            let vertices = &self.navmesh.vertices;
+            let edge = Segment::new(vertices[triangle[node % 3]], vertices[triangle[(node % 3 + 1) % 3]]);
+            edge.closest_points(&Segment::new(self.start, self.end)).0
+        }
+    }
+}
+
+impl AStarInput for NavMeshAStarInput<'_> {
+    fn neighbors(&self, node: usize) -> &[usize] {
+        if node == self.start() {
+            &self.start_edges
+        } else if node == self.end() {
+            &[]
+        } else if let Some((_, next_edges)) = self.end_edges.iter().find(|(edge, _)| *edge == node) {
+            next_edges
+        } else {
+            &self.navmesh.edge_adjacency[node]
+        }
+    }
+
+    fn distance(&self, from: usize, to: usize) -> N64 {
+        n64(self.get_position(from).dist(self.get_position(to)))
+    }
+
+    fn heuristic(&self, node: usize) -> N64 {
+        n64(self.get_position(node).dist(self.end))
+    }
+
+    fn len(&self) -> usize {
+        self.navmesh.triangles.len() * 3 + 2
+    }
+
+    fn start(&self) -> usize {
+        self.navmesh.triangles.len() * 3
+    }
+
+    fn end(&self) -> usize {
+        self.navmesh.triangles.len() * 3 + 1
+    }
+}
+
+impl PathFinder for NavMesh {
+    fn find_path(&self, start: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
+        NavMesh::find_path(self, start, end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{path_length, Polygon};
+    use crate::navigation::Navigation;
+    use crate::test_utils::{properly_crosses, square, square_grid};
+
+    fn bounds() -> Aabb {
+        Aabb::new(Vec2::new(-50., -50.), Vec2::new(150., 150.))
+    }
+
+    #[test]
+    fn constrained_delaunay() {
+        let obstacles = vec![square(10., 10., 10.), square(15., 30., 10.)];
+        let navmesh = NavMesh::new(&obstacles, bounds());
+        // Every obstacle edge is an edge of the mesh
+        for obstacle in &obstacles {
+            for segment in obstacle.shape().segments() {
+                assert!(navmesh
+                    .walkable_triangles()
+                    .iter()
+                    .any(|triangle| triangle.segments().into_iter().any(|edge| edge == segment)));
+            }
+        }
+        let area: f64 = navmesh.walkable_triangles().iter().map(Shape::area).sum();
+        assert!((area - (200. * 200. - 200.)).abs() < 1e-6);
+        assert!(navmesh.locate(Vec2::new(15., 15.)).is_none());
+    }
+
+    #[test]
+    fn touching_obstacles() {
+        // A corner of the second square lies on the top edge of the first, which is split there
+        let obstacles = vec![square(10., 10., 10.), square(15., 20., 10.)];
+        let navmesh = NavMesh::new(&obstacles, bounds());
+        let area: f64 = navmesh.walkable_triangles().iter().map(Shape::area).sum();
+        assert!((area - (200. * 200. - 200.)).abs() < 1e-6);
+        assert_eq!(
+            navmesh.find_path(Vec2::new(12., 25.), Vec2::new(12., 5.)).unwrap(),
+            vec![
+                Vec2::new(12., 25.),
+                Vec2::new(10., 20.),
+                Vec2::new(10., 10.),
+                Vec2::new(12., 5.)
+            ]
+        );
+    }
+
+    #[test]
+    fn same_paths_as_navigation() {
+        let obstacles = || vec![square(10., 10., 10.), square(30., 0., 10.)];
+        let navigation = Navigation::new(obstacles());
+        let navmesh = NavMesh::new(&obstacles(), bounds());
+        let (start, end) = (Vec2::new(0., 15.), Vec2::new(50., 5.));
+        // The visibility graph also passes through (30, 10), which is on a straight line
+        let path = navmesh.find_path(start, end).unwrap();
+        assert_eq!(
+            path,
+            vec![
+                Vec2::new(0., 15.),
+                Vec2::new(10., 10.),
+                Vec2::new(20., 10.),
+                Vec2::new(40., 10.),
+                Vec2::new(50., 5.)
+            ]
+        );
+        assert_eq!(
+            path_length(&path),
+            path_length(&navigation.find_path(start, end).unwrap())
+        );
+        assert_eq!(
+            navmesh.find_path(Vec2::new(0., 0.), Vec2::new(5., 5.)).unwrap(),
+            vec![Vec2::new(0., 0.), Vec2::new(5., 5.)]
+        );
+        assert_eq!(navmesh.find_path(start, Vec2::new(15., 15.)), None);
+    }
+
+    #[test]
+    fn path_lengths_close_to_navigation() {
+        // A grid of squares, with some of them overlapping
+        let obstacles = square_grid(Vec2::zero(), 5, 20., Vec2::new(1., 0.), |i, j| {
+            if (i + j) % 3 == 0 {
+                14.
+            } else {
+                8.
+            }
+        });
+        let navmesh = NavMesh::new(&obstacles, bounds());
+        let shapes: Vec<Shape> = obstacles.iter().map(|obstacle| obstacle.shape().clone()).collect();
+        let navigation = Navigation::new(obstacles);
+        let points = [
+            Vec2::new(-10., -10.),
+            Vec2::new(110., 105.),
+            Vec2::new(50., -5.),
+            Vec2::new(-5., 70.),
+            Vec2::new(69., 50.),
+            Vec2::new(90., 10.),
+        ];
+        let finders: [&dyn PathFinder; 2] = [&navigation, &navmesh];
+        for &start in &points {
+            for &end in &points {
+                let [visibility_path, navmesh_path] =
+                    [finders[0].find_path(start, end), finders[1].find_path(start, end)];
+                let (visibility_path, navmesh_path) = (visibility_path.unwrap(), navmesh_path.unwrap());
+                // The path goes around the obstacles, possibly along their edges, but may not be
+                // the shortest
+                for segment in navmesh_path.windows(2) {
+                    let (a, b) = (segment[0], segment[1]);
+                    for edge in shapes.iter().flat_map(Shape::segments) {
+                        assert!(!properly_crosses(Segment::new(a, b), edge));
+                    }
+                    let middle = (a + b) / 2.;
+                    let on_edge = navigation.clearance_at(middle).unwrap().distance < 1e-9;
+                    assert!(on_edge || !shapes.iter().any(|shape| shape.contains(middle)));
+                }
+                let optimal = path_length(&visibility_path);
+                let length = path_length(&navmesh_path);
+                assert!(length >= optimal - 1e-9);
+                assert!(length <= optimal * 1.1, "{} > {} * 1.1", length, optimal);
+            }
+        }
+    }
+
+    #[test]
+    fn polylines_and_holes() {
+        let wall = || NavigationObstacle::new_polyline(vec![Vec2::new(10., -5.), Vec2::new(10., 20.)]);
+        let navmesh = NavMesh::new(&[wall()], bounds());
+        let (start, end) = (Vec2::new(0., 10.), Vec2::new(20., 10.));
+        assert_eq!(
+            navmesh.find_path(start, end),
+            Navigation::new(vec![wall()]).find_path(start, end)
+        );
+        let room = Polygon::new(
+            square(0., 0., 30.).shape().clone(),
+            vec![square(10., 10., 10.).shape().clone()],
+        );
+        let navmesh = NavMesh::new(&[NavigationObstacle::from_polygon(&room)], bounds());
+        let inside = Vec2::new(12., 12.);
+        assert_eq!(navmesh.find_path(Vec2::new(-10., 15.), inside), None);
+        assert_eq!(
+            navmesh.find_path(inside, Vec2::new(18., 15.)),
+            Some(vec![inside, Vec2::new(18., 15.)])
+        );
+    }
+}
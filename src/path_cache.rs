@@ -0,0 +1,102 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::geometry::Vec2;
+
+/// The cells of a grid that the start and the end point fall into.
+pub(crate) type PathCacheKey = (i64, i64, i64, i64);
+
+/// Remembers the paths of recent queries, forgetting the least recently used one when full. See
+/// `Navigation::enable_path_cache`.
+pub(crate) struct PathCache {
+    capacity: usize,
+    cell_size: f64,
+    /// Every path, with the time it has been used last.
+    entries: HashMap<PathCacheKey, (Vec<Vec2>, u64)>,
+    /// The keys of all entries, by the time they have been used last.
+    usage: BTreeMap<u64, PathCacheKey>,
+    time: u64,
+}
+
+impl PathCache {
+    pub(crate) fn new(capacity: usize, cell_size: f64) -> Self {
+        debug_assert!(cell_size > 0.);
+        PathCache {
+            capacity,
+            cell_size,
+            entries: HashMap::new(),
+            usage: BTreeMap::new(),
+            time: 0,
+        }
+    }
+    pub(crate) fn key(&self, start: Vec2, end: Vec2) -> PathCacheKey {
+        let cell = |coordinate: f64| (coordinate / self.cell_size).floor() as i64;
+        (cell(start.x), cell(start.y), cell(end.x), cell(end.y))
+    }
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.usage.clear();
+    }
+    fn touch(&mut self, key: PathCacheKey) -> Option<&Vec<Vec2>> {
+        let (path, last_used) = self.entries.get_mut(&key)?;
+        self.usage.remove(last_used);
+        self.time += 1;
+        *last_used = self.time;
+        self.usage.insert(self.time, key);
+        Some(path)
+    }
+    pub(crate) fn get(&mut self, key: PathCacheKey) -> Option<Vec<Vec2>> {
+        self.touch(key).cloned()
+    }
+    pub(crate) fn insert(&mut self, key: PathCacheKey, path: Vec<Vec2>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Some((_, last_used)) = self.entries.remove(&key) {
+            self.usage.remove(&last_used);
+        } else if self.entries.len() >= self.capacity {
+            let oldest = *self.usage.keys().next().unwrap();
+            let least_recently_used = self.usage.remove(&oldest).unwrap();
+            self.entries.remove(&least_recently_used);
+        }
+        self.time += 1;
+        self.entries.insert(key, (path, self.time));
+        self.usage.insert(self.time, key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantized_keys() {
+        let cache = PathCache::new(4, 10.);
+        assert_eq!(cache.key(Vec2::new(1., 19.), Vec2::new(-1., 100.)), (0, 1, -1, 10));
+        assert_eq!(
+            cache.key(Vec2::new(1., 19.), Vec2::new(-1., 100.)),
+            cache.key(Vec2::new(9., 11.), Vec2::new(-9., 109.))
+        );
+    }
+
+    #[test]
+    fn least_recently_used_is_evicted() {
+        let mut cache = PathCache::new(2, 1.);
+        let path = |x: f64| vec![Vec2::new(x, 0.), Vec2::new(x, 1.)];
+        cache.insert((0, 0, 0, 0), path(0.));
+        cache.insert((1, 0, 0, 0), path(1.));
+        assert_eq!(cache.get((0, 0, 0, 0)), Some(path(0.)));
+        cache.insert((2, 0, 0, 0), path(2.));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get((1, 0, 0, 0)), None);
+        assert_eq!(cache.get((0, 0, 0, 0)), Some(path(0.)));
+        // Replacing an entry does not evict another one
+        cache.insert((2, 0, 0, 0), path(3.));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get((2, 0, 0, 0)), Some(path(3.)));
+        cache.clear();
+        assert_eq!(cache.get((0, 0, 0, 0)), None);
+    }
+}
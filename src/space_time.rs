@@ -0,0 +1,632 @@
+//! Path finding among obstacles that move on known schedules, such as patrolling guards or
+//! trains. Paths are timed: every waypoint comes with the time the agent passes it, and the agent
+//! may wait at a waypoint until the way is clear.
+//!
+//! The agent moves between waypoints at a constant speed. Waypoints are the corners of the static
+//! obstacles and points just outside of the area every moving obstacle sweeps. Waiting is done in
+//! steps of `SpaceTimeOptions::wait_step`, so the paths are the fastest only up to these choices.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use noisy_float::prelude::*;
+use noisy_float::types::N64;
+
+use crate::geometry::{Segment, Shape, Vec2};
+use crate::navigation::{Navigation, NavigationObstacle};
+
+/// How far outside of the swept area of a moving obstacle its waypoints are placed, relative to
+/// their distance from the origin.
+const CORNER_OFFSET: f64 = 1e-6;
+
+/// How close to an edge of a static obstacle a point counts as on the edge, see
+/// `SpaceTimeNavigation::is_path_free`.
+const EDGE_TOLERANCE: f64 = 1e-9;
+
+/// An obstacle moving with constant velocity during a time interval. It does not exist outside of
+/// the interval, so an obstacle that follows a route is made of one `MovingObstacle` per leg, see
+/// `MovingObstacle::route`.
+#[derive(Debug, Clone)]
+pub struct MovingObstacle {
+    /// The shape at `start_time`.
+    shape: Shape,
+    start_time: f64,
+    end_time: f64,
+    velocity: Vec2,
+}
+
+impl MovingObstacle {
+    pub fn new(vertices: Vec<Vec2>, start_time: f64, end_time: f64, velocity: Vec2) -> Self {
+        debug_assert!(start_time <= end_time);
+        MovingObstacle {
+            shape: Shape::new(vertices),
+            start_time,
+            end_time,
+            velocity,
+        }
+    }
+    /// An obstacle that is moved by `offset` at `time` for every `(time, offset)` in `keyframes`,
+    /// and moves in a straight line in between. The keyframes have to be ordered by time.
+    pub fn route(vertices: Vec<Vec2>, keyframes: &[(f64, Vec2)]) -> Vec<MovingObstacle> {
+        let shape = Shape::new(vertices);
+        keyframes
+            .windows(2)
+            .map(|legs| {
+                let ((start_time, start_offset), (end_time, end_offset)) = (legs[0], legs[1]);
+                let duration = end_time - start_time;
+                MovingObstacle {
+                    shape: Shape::new(shape.vertices.iter().map(|&vertex| vertex + start_offset).collect()),
+                    start_time,
+                    end_time,
+                    velocity: if duration > 0. {
+                        (end_offset - start_offset) / duration
+                    } else {
+                        Vec2::zero()
+                    },
+                }
+            })
+            .collect()
+    }
+    pub fn start_time(&self) -> f64 {
+        self.start_time
+    }
+    pub fn end_time(&self) -> f64 {
+        self.end_time
+    }
+    /// The shape at `time`, or `None` if the obstacle does not exist at that time.
+    pub fn shape_at(&self, time: f64) -> Option<Shape> {
+        if time < self.start_time || time > self.end_time {
+            return None;
+        }
+        let offset = self.velocity * (time - self.start_time);
+        Some(Shape::new(
+            self.shape.vertices.iter().map(|&vertex| vertex + offset).collect(),
+        ))
+    }
+    /// The convex area covered by the obstacle while it moves.
+    fn swept_hull(&self) -> Shape {
+        let offset = self.velocity * (self.end_time - self.start_time);
+        let mut vertices = self.shape.vertices.clone();
+        vertices.extend(self.shape.vertices.iter().map(|&vertex| vertex + offset));
+        Shape::new(vertices).convex_hull()
+    }
+    /// Whether an agent moving in a straight line from `from` at `from_time` to `to` at `to_time`
+    /// touches the obstacle.
+    pub fn hits(&self, from: Vec2, from_time: f64, to: Vec2, to_time: f64) -> bool {
+        let overlap_start = from_time.max(self.start_time);
+        let overlap_end = to_time.min(self.end_time);
+        if overlap_start > overlap_end {
+            return false;
+        }
+        let agent_at = |time: f64| {
+            if to_time > from_time {
+                from + (to - from) * ((time - from_time) / (to_time - from_time))
+            } else {
+                from
+            }
+        };
+        // Seen from the obstacle, the agent still moves in a straight line
+        let relative_at = |time: f64| agent_at(time) - self.velocity * (time - self.start_time);
+        let relative_path = Segment::new(relative_at(overlap_start), relative_at(overlap_end));
+        self.shape.contains(relative_path.p0)
+            || self
+                .shape
+                .segments()
+                .into_iter()
+                .any(|edge| relative_path.intersect(&edge).is_some())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimedWaypoint {
+    pub position: Vec2,
+    pub time: f64,
+}
+
+/// A path whose waypoints are passed at the given times, in straight lines at constant speed in
+/// between. Two consecutive waypoints at the same position mean waiting there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedPath {
+    pub waypoints: Vec<TimedWaypoint>,
+}
+
+impl TimedPath {
+    pub fn start_time(&self) -> f64 {
+        self.waypoints[0].time
+    }
+    pub fn end_time(&self) -> f64 {
+        self.waypoints[self.waypoints.len() - 1].time
+    }
+    /// Where the agent is at `time`, or `None` if the path has not started or is already over.
+    pub fn position_at(&self, time: f64) -> Option<Vec2> {
+        let leg = self
+            .waypoints
+            .windows(2)
+            .find(|leg| leg[0].time <= time && time <= leg[1].time)?;
+        let duration = leg[1].time - leg[0].time;
+        if duration <= 0. {
+            return Some(leg[1].position);
+        }
+        Some(leg[0].position + (leg[1].position - leg[0].position) * ((time - leg[0].time) / duration))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SpaceTimeOptions {
+    /// How long the agent waits at once. Shorter steps find faster paths, but take longer to find.
+    pub wait_step: f64,
+    /// Give up on paths that take longer than this.
+    pub max_duration: Option<f64>,
+}
+
+impl Default for SpaceTimeOptions {
+    fn default() -> Self {
+        SpaceTimeOptions {
+            wait_step: 0.25,
+            max_duration: None,
+        }
+    }
+}
+
+/// Static obstacles, which are never crossed, together with moving ones, which are avoided at the
+/// time they are in the way.
+pub struct SpaceTimeNavigation {
+    navigation: Navigation,
+    moving_obstacles: Vec<MovingObstacle>,
+    /// Waypoints just outside of the area swept by every moving obstacle. They are numbered after
+    /// the nodes of the navigation graph.
+    corners: Vec<Vec2>,
+    /// The neighbors of every node of the navigation graph and of every corner, without the start
+    /// and end point of a query.
+    neighbors: Vec<Vec<usize>>,
+    /// After this, nothing moves anymore and waiting is pointless.
+    last_end_time: f64,
+}
+
+/// A waypoint reached at some time, with the index of the state it has been reached from.
+struct SearchState {
+    waypoint: usize,
+    time: f64,
+    /// The number of `SpaceTimeOptions::wait_step` since the start time, rounded down. Waiting
+    /// always advances it by one, no matter how the time is rounded. All times after the last
+    /// obstacle stopped moving share the slot `i64::MAX`, since arriving earlier is never worse
+    /// then, and the search has to end even if the end point cannot be reached.
+    time_slot: i64,
+    parent: usize,
+}
+
+impl SpaceTimeNavigation {
+    pub fn new(obstacles: Vec<NavigationObstacle>, moving_obstacles: Vec<MovingObstacle>) -> Self {
+        let navigation = Navigation::new(obstacles);
+        let graph_len = navigation.graph_len();
+        let mut corners = vec![];
+        for moving_obstacle in &moving_obstacles {
+            let hull = moving_obstacle.swept_hull();
+            if hull.vertices.len() < 2 {
+                continue;
+            }
+            for (index, &vertex) in hull.vertices.iter().enumerate() {
+                let inward =
+                    (hull.prev_vertex(index) - vertex).normalize() + (hull.next_vertex(index) - vertex).normalize();
+                if inward.is_zero() {
+                    continue;
+                }
+                let corner = vertex - inward.normalize() * (vertex.magnitude().max(1.) * CORNER_OFFSET);
+                if navigation.nearest_free_point(corner) == corner {
+                    corners.push(corner);
+                }
+            }
+        }
+
+        let mut neighbors: Vec<Vec<usize>> = (0..graph_len)
+            .map(|node_id| navigation.graph_neighbors(node_id).to_vec())
+            .collect();
+        neighbors.resize(graph_len + corners.len(), vec![]);
+        for (index, &corner) in corners.iter().enumerate() {
+            let corner_id = graph_len + index;
+            for node_id in navigation.connectable_nodes(corner) {
+                neighbors[corner_id].push(node_id);
+                neighbors[node_id].push(corner_id);
+            }
+            for (other_index, &other_corner) in corners[..index].iter().enumerate() {
+                if navigation.has_line_of_sight(corner, other_corner) {
+                    neighbors[corner_id].push(graph_len + other_index);
+                    neighbors[graph_len + other_index].push(corner_id);
+                }
+            }
+        }
+        let last_end_time = moving_obstacles
+            .iter()
+            .map(|moving_obstacle| moving_obstacle.end_time)
+            .fold(f64::NEG_INFINITY, f64::max);
+        SpaceTimeNavigation {
+            navigation,
+            moving_obstacles,
+            corners,
+            neighbors,
+            last_end_time,
+        }
+    }
+    pub fn navigation(&self) -> &Navigation {
+        &self.navigation
+    }
+    pub fn moving_obstacles(&self) -> &[MovingObstacle] {
+        &self.moving_obstacles
+    }
+    fn is_leg_free(&self, from: TimedWaypoint, to: TimedWaypoint) -> bool {
+        !self
+            .moving_obstacles
+            .iter()
+            .any(|moving_obstacle| moving_obstacle.hits(from.position, from.time, to.position, to.time))
+    }
+    /// Whether the segment from `a` to `b` stays out of the static obstacles. Unlike
+    /// `Navigation::has_line_of_sight`, this also decides segments that start at a vertex and go
+    /// through the obstacle, and allows walking along the edges of an obstacle.
+    fn is_statically_free(&self, a: Vec2, b: Vec2) -> bool {
+        let shapes: Vec<&Shape> = self
+            .navigation
+            .obstacles()
+            .iter()
+            .map(NavigationObstacle::shape)
+            .collect();
+        let segment = Segment::new(a, b);
+        let length_squared = a.dist_squared(b);
+        let t_at = |point: Vec2| {
+            if length_squared > 0. {
+                ((point - a).dot(b - a) / length_squared).clamp(0., 1.)
+            } else {
+                0.
+            }
+        };
+        // The segment is split where it meets the edges of the obstacles, and every part is either
+        // entirely inside or entirely outside of an obstacle
+        let mut splits = vec![0., 1.];
+        for shape in &shapes {
+            for edge in shape.segments() {
+                if let Some(point) = segment.intersect(&edge) {
+                    splits.push(t_at(point));
+                }
+                for vertex in [edge.p0, edge.p1] {
+                    if segment.closest_point(vertex).dist(vertex) < EDGE_TOLERANCE {
+                        splits.push(t_at(vertex));
+                    }
+                }
+            }
+        }
+        splits.sort_by(|t0, t1| t0.partial_cmp(t1).unwrap());
+        splits.windows(2).all(|interval| {
+            let middle = a + (b - a) * ((interval[0] + interval[1]) / 2.);
+            shapes.iter().all(|shape| {
+                !shape.contains(middle)
+                    || shape
+                        .segments()
+                        .into_iter()
+                        .any(|edge| edge.closest_point(middle).dist(middle) < EDGE_TOLERANCE)
+            })
+        })
+    }
+    /// Whether `path` keeps out of all obstacles, the moving ones at the time they are passed.
+    pub fn is_path_free(&self, path: &TimedPath) -> bool {
+        path.waypoints.windows(2).all(|leg| {
+            let (from, to) = (leg[0], leg[1]);
+            from.time <= to.time && self.is_statically_free(from.position, to.position) && self.is_leg_free(from, to)
+        })
+    }
+    /// Finds the path from `start` to `end` that arrives the earliest, for an agent leaving at
+    /// `start_time` and moving at `speed`.
+    pub fn find_path(&self, start: Vec2, end: Vec2, start_time: f64, speed: f64) -> Option<TimedPath> {
+        self.find_path_with_options(start, end, start_time, speed, &SpaceTimeOptions::default())
+    }
+    pub fn find_path_with_options(
+        &self,
+        start: Vec2,
+        end: Vec2,
+        start_time: f64,
+        speed: f64,
+        options: &SpaceTimeOptions,
+    ) -> Option<TimedPath> {
+        debug_assert!(speed > 0. && options.wait_step > 0.);
+        let start_id = self.neighbors.len();
+        let end_id = start_id + 1;
+        let position = |waypoint: usize| {
+            if waypoint == start_id {
+                start
+            } else if waypoint == end_id {
+                end
+            } else if waypoint < self.navigation.graph_len() {
+                self.navigation.graph_position(waypoint)
+            } else {
+                self.corners[waypoint - self.navigation.graph_len()]
+            }
+        };
+        // The start point is only ever left, so only the end point has to be added to the other
+        // waypoints
+        let mut start_neighbors = self.navigation.connectable_nodes(start);
+        let mut sees_end = vec![false; start_id + 1];
+        for node_id in self.navigation.connectable_nodes(end) {
+            sees_end[node_id] = true;
+        }
+        for (index, &corner) in self.corners.iter().enumerate() {
+            let corner_id = self.navigation.graph_len() + index;
+            if self.navigation.has_line_of_sight(start, corner) {
+                start_neighbors.push(corner_id);
+            }
+            sees_end[corner_id] = self.navigation.has_line_of_sight(corner, end);
+        }
+        sees_end[start_id] = self.navigation.has_line_of_sight(start, end);
+        let end_time_limit = options
+            .max_duration
+            .map_or(f64::INFINITY, |max_duration| start_time + max_duration);
+        let heuristic = |waypoint: usize, time: f64| n64(time + position(waypoint).dist(end) / speed);
+
+        let time_slot_at = |time: f64, time_slot: i64| {
+            if time >= self.last_end_time {
+                i64::MAX
+            } else {
+                time_slot
+            }
+        };
+        let mut states = vec![SearchState {
+            waypoint: start_id,
+            time: start_time,
+            time_slot: time_slot_at(start_time, 0),
+            parent: usize::MAX,
+        }];
+        // The earliest time every waypoint has been reached at, for every time slot
+        let mut reached: HashMap<(usize, i64), f64> = HashMap::new();
+        let mut open_queue: BinaryHeap<Reverse<(N64, usize)>> = BinaryHeap::new();
+        open_queue.push(Reverse((heuristic(start_id, start_time), 0)));
+        let mut expanded = HashSet::new();
+        while let Some(Reverse((_, state_index))) = open_queue.pop() {
+            let SearchState {
+                waypoint,
+                time,
+                time_slot,
+                ..
+            } = states[state_index];
+            if waypoint == end_id {
+                let path = self.reconstruct_path(&states, state_index, position);
+                debug_assert!(self.is_path_free(&path));
+                return Some(path);
+            }
+            if !expanded.insert((waypoint, time_slot)) {
+                continue;
+            }
+            let here = TimedWaypoint {
+                position: position(waypoint),
+                time,
+            };
+            let mut successors = vec![];
+            if time < self.last_end_time {
+                let next_time = time + options.wait_step;
+                successors.push((waypoint, next_time, time_slot_at(next_time, time_slot + 1)));
+            }
+            let neighbors = if waypoint == start_id {
+                &start_neighbors
+            } else {
+                &self.neighbors[waypoint]
+            };
+            for &neighbor in neighbors.iter().chain(sees_end[waypoint].then_some(&end_id)) {
+                let next_time = time + here.position.dist(position(neighbor)) / speed;
+                let next_time_slot = ((next_time - start_time) / options.wait_step).floor() as i64;
+                successors.push((
+                    neighbor,
+                    next_time,
+                    time_slot_at(next_time, next_time_slot.max(time_slot)),
+                ));
+            }
+            for (next_waypoint, next_time, next_time_slot) in successors {
+                if next_time > end_time_limit {
+                    continue;
+                }
+                let next_slot = (next_waypoint, next_time_slot);
+                if matches!(reached.get(&next_slot), Some(&reached_time) if reached_time <= next_time) {
+                    continue;
+                }
+                let next = TimedWaypoint {
+                    position: position(next_waypoint),
+                    time: next_time,
+                };
+                if !self.is_leg_free(here, next) {
+                    continue;
+                }
+                reached.insert(next_slot, next_time);
+                states.push(SearchState {
+                    waypoint: next_waypoint,
+                    time: next_time,
+                    time_slot: next_time_slot,
+                    parent: state_index,
+                });
+                open_queue.push(Reverse((heuristic(next_waypoint, next_time), states.len() - 1)));
+            }
+        }
+        None
+    }
+    fn reconstruct_path(&self, states: &[SearchState], end: usize, position: impl Fn(usize) -> Vec2) -> TimedPath {
+        let mut waypoints: Vec<TimedWaypoint> = vec![];
+        let mut state_index = end;
+        while state_index != usize::MAX {
+            let state = &states[state_index];
+            let waypoint = TimedWaypoint {
+                position: position(state.waypoint),
+                time: state.time,
+            };
+            // Waiting for multiple steps at once only needs its first and last waypoint
+            if waypoints.len() >= 2
+                && waypoints[waypoints.len() - 1].position == waypoint.position
+                && waypoints[waypoints.len() - 2].position == waypoint.position
+            {
+                waypoints.pop();
+            }
+            waypoints.push(waypoint);
+            state_index = state.parent;
+        }
+        waypoints.reverse();
+        TimedPath { waypoints }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Polygon;
+
+    fn rectangle(x0: f64, y0: f64, x1: f64, y1: f64) -> Vec<Vec2> {
+        vec![
+            Vec2::new(x0, y0),
+            Vec2::new(x1, y0),
+            Vec2::new(x1, y1),
+            Vec2::new(x0, y1),
+        ]
+    }
+
+    #[test]
+    fn moving_obstacle_hits() {
+        let train = MovingObstacle::new(rectangle(-20., -5., -10., 5.), 0., 10., Vec2::new(10., 0.));
+        assert_eq!(train.shape_at(2.), Some(Shape::new(rectangle(0., -5., 10., 5.))));
+        assert_eq!(train.shape_at(11.), None);
+        // Standing on the tracks while the train passes
+        assert!(train.hits(Vec2::new(5., 0.), 0., Vec2::new(5., 0.), 10.));
+        assert!(!train.hits(Vec2::new(5., 0.), 3.6, Vec2::new(5., 0.), 10.));
+        // Crossing the tracks in front of and behind the train
+        assert!(!train.hits(Vec2::new(5., -10.), 0., Vec2::new(5., 10.), 1.));
+        assert!(train.hits(Vec2::new(5., -10.), 1., Vec2::new(5., 10.), 2.));
+        assert!(!train.hits(Vec2::new(5., -10.), 3., Vec2::new(5., 10.), 4.));
+        // After the train is gone
+        assert!(!train.hits(Vec2::new(85., -10.), 11., Vec2::new(85., 10.), 12.));
+    }
+
+    #[test]
+    fn wait_for_train() {
+        // Walls on both sides of a level crossing, so going around the train is not possible
+        let navigation = SpaceTimeNavigation::new(
+            vec![
+                NavigationObstacle::new(rectangle(-300., -10., -2., 10.)),
+                NavigationObstacle::new(rectangle(2., -10., 600., 10.)),
+            ],
+            vec![MovingObstacle::new(
+                rectangle(-200., -5., -50., 5.),
+                0.,
+                20.,
+                Vec2::new(50., 0.),
+            )],
+        );
+        let (start, end) = (Vec2::new(0., -20.), Vec2::new(0., 20.));
+        let options = SpaceTimeOptions {
+            wait_step: 0.1,
+            ..SpaceTimeOptions::default()
+        };
+        let path = navigation
+            .find_path_with_options(start, end, 0., 10., &options)
+            .unwrap();
+        assert!(navigation.is_path_free(&path));
+        assert_eq!(
+            path.waypoints[0],
+            TimedWaypoint {
+                position: start,
+                time: 0.
+            }
+        );
+        assert_eq!(path.waypoints.last().unwrap().position, end);
+        // The tail of the train passes at 4 seconds, and it takes the agent 1.5 seconds to get to the
+        // tracks and 4 seconds to cross the level crossing
+        assert!(path.end_time() >= 6.5 && path.end_time() <= 6.5 + options.wait_step + 1e-9);
+        assert!(path
+            .waypoints
+            .windows(2)
+            .any(|leg| leg[0].position == leg[1].position && leg[0].time < leg[1].time));
+        assert_eq!(path.position_at(path.end_time()), Some(end));
+        assert_eq!(path.position_at(path.end_time() + 1.), None);
+
+        // Not waiting
+        let direct = TimedPath {
+            waypoints: vec![
+                TimedWaypoint {
+                    position: start,
+                    time: 0.,
+                },
+                TimedWaypoint {
+                    position: end,
+                    time: 4.,
+                },
+            ],
+        };
+        assert!(!navigation.is_path_free(&direct));
+        let hurried = SpaceTimeOptions {
+            max_duration: Some(6.),
+            ..options
+        };
+        assert_eq!(navigation.find_path_with_options(start, end, 0., 10., &hurried), None);
+        // Leaving after the train
+        let path = navigation.find_path(start, end, 10., 10.).unwrap();
+        assert_eq!(path.waypoints.len(), 2);
+        assert_eq!(path.end_time(), 14.);
+    }
+
+    #[test]
+    fn unreachable() {
+        // The end point is in a closed room, while a guard walks by outside
+        let room = Polygon::new(
+            Shape::new(rectangle(0., 0., 40., 40.)),
+            vec![Shape::new(rectangle(10., 10., 30., 30.))],
+        );
+        let navigation = SpaceTimeNavigation::new(
+            vec![NavigationObstacle::from_polygon(&room)],
+            MovingObstacle::route(
+                rectangle(-10., -10., -8., -8.),
+                &[
+                    (0., Vec2::new(0., 0.)),
+                    (5., Vec2::new(0., 50.)),
+                    (10., Vec2::new(0., 0.)),
+                ],
+            ),
+        );
+        let (start, end) = (Vec2::new(-20., 20.), Vec2::new(20., 20.));
+        assert_eq!(navigation.navigation().find_path(start, end), None);
+        assert_eq!(navigation.find_path(start, end, 0., 5.), None);
+        assert_eq!(navigation.find_path(start, end, 20., 5.), None);
+    }
+
+    #[test]
+    fn avoid_patrol() {
+        // A guard walking up and down across the way, passing y = 15 from 2.17 to 2.83 seconds,
+        // from 7.17 to 7.83 and from 12.17 to 12.83
+        let guard = MovingObstacle::route(
+            rectangle(-2., -2., 2., 2.),
+            &[
+                (0., Vec2::new(0., 0.)),
+                (5., Vec2::new(0., 30.)),
+                (10., Vec2::new(0., 0.)),
+                (15., Vec2::new(0., 30.)),
+            ],
+        );
+        assert_eq!(guard.len(), 3);
+        assert_eq!(guard[1].shape_at(7.5), Some(Shape::new(rectangle(-2., 13., 2., 17.))));
+        let navigation = SpaceTimeNavigation::new(vec![], guard);
+        let (start, end) = (Vec2::new(-20., 15.), Vec2::new(20., 15.));
+        // Straight ahead, the agent is in the way of the guard from 3.6 to 4.4 seconds after leaving
+        for &(start_time, blocked) in &[(0., false), (3.3, true), (6., false), (8., true)] {
+            let straight = TimedPath {
+                waypoints: vec![
+                    TimedWaypoint {
+                        position: start,
+                        time: start_time,
+                    },
+                    TimedWaypoint {
+                        position: end,
+                        time: start_time + 8.,
+                    },
+                ],
+            };
+            assert_eq!(navigation.is_path_free(&straight), !blocked);
+            let path = navigation.find_path(start, end, start_time, 5.).unwrap();
+            assert!(navigation.is_path_free(&path));
+            assert_eq!(path.start_time(), start_time);
+            if blocked {
+                assert!(path.end_time() > straight.end_time());
+            } else {
+                assert_eq!(path, straight);
+            }
+        }
+    }
+}
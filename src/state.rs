@@ -2,22 +2,26 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 use crate::canvas::Canvas;
-use crate::geometry::{Segment, Shape, Vec2};
+use crate::geometry::{Polygon, Segment, Shape, Vec2};
 use crate::input::Input;
-use crate::navigation::{Navigation, NavigationInternal, NavigationObstacle};
+use crate::navigation::{Navigation, NavigationInternal, NavigationObstacle, PathTree};
 
 pub enum Placing {
     Start,
     End,
     Obstacle(Shape),
+    /// Cuts the shape out of all obstacles.
+    Cut(Shape),
 }
 
 pub struct State {
-    obstacles: Vec<Shape>,
+    obstacles: Vec<Polygon>,
     start: Option<Vec2>,
     end: Option<Vec2>,
     placing: Option<Placing>,
     navigation: Navigation,
+    /// Rooted at the start point, kept while only the end point moves
+    path_tree: Option<PathTree>,
     current_path: Vec<Vec2>,
     displaying_actual_obstacles: bool,
     displaying_navigation_graph: bool,
@@ -65,6 +69,7 @@ impl State {
             end: None,
             placing: None,
             navigation: Navigation::new(vec![]),
+            path_tree: None,
             current_path: vec![],
             displaying_actual_obstacles: false,
             displaying_navigation_graph: false,
@@ -89,6 +94,8 @@ impl State {
 
         if input.is_frame_key_pressed("KeyO") {
             self.set_placing(Placing::Obstacle(Shape::new_empty()));
+        } else if input.is_frame_key_pressed("KeyC") {
+            self.set_placing(Placing::Cut(Shape::new_empty()));
         } else if input.is_frame_key_pressed("KeyS") {
             self.set_placing(Placing::Start);
         } else if input.is_frame_key_pressed("KeyE") {
@@ -97,10 +104,10 @@ impl State {
 
         if let Some(Placing::Start) = self.placing {
             self.start = Some(input.mouse_pos());
-            self.endpoint_updated();
+            self.start_updated();
         } else if let Some(Placing::End) = self.placing {
             self.end = Some(input.mouse_pos());
-            self.endpoint_updated();
+            self.end_updated();
         }
 
         if let Some(mouse_click) = input.frame_mouse_clicked() {
@@ -111,12 +118,12 @@ impl State {
         self.navigation = Navigation::new(
             self.obstacles
                 .iter()
-                .map(|obstacle| {
-                    let obstacle = NavigationObstacle::new(obstacle.vertices.clone());
+                .flat_map(|obstacle| {
+                    let obstacle = NavigationObstacle::from_polygon(obstacle);
                     if self.reserved_path_width_level == 0 {
-                        obstacle
+                        vec![obstacle]
                     } else {
-                        obstacle.expand(
+                        obstacle.expand_decomposed(
                             self.reserved_path_width_level as f64,
                             std::f64::consts::PI / (self.reserved_path_width_level as f64 / 5. + 2.).min(10.),
                         )
@@ -124,18 +131,26 @@ impl State {
                 })
                 .collect(),
         );
+        self.path_tree = None;
         self.find_path();
     }
-    pub fn endpoint_updated(&mut self) {
+    pub fn start_updated(&mut self) {
+        self.path_tree = None;
+        self.find_path();
+    }
+    pub fn end_updated(&mut self) {
         self.find_path();
     }
     fn find_path(&mut self) {
         if let (Some(start), Some(end)) = (self.start, self.end) {
-            self.current_path = if let Some(path) = self.navigation.find_path(start, end) {
-                path
-            } else {
-                vec![]
-            }
+            // The expanded obstacles are invisible by default, so it is easy to click into them
+            let navigation = &self.navigation;
+            let path_tree = self
+                .path_tree
+                .get_or_insert_with(|| navigation.path_tree(navigation.nearest_free_point(start)));
+            self.current_path = path_tree
+                .path_to(navigation, navigation.nearest_free_point(end))
+                .unwrap_or_default();
         } else {
             self.current_path = vec![];
         }
@@ -146,7 +161,7 @@ impl State {
     pub fn click(&mut self, mouse_pos: (i32, i32)) {
         #[allow(clippy::single_match)]
         match &mut self.placing {
-            Some(Placing::Obstacle(shape)) => {
+            Some(Placing::Obstacle(shape)) | Some(Placing::Cut(shape)) => {
                 let pos = mouse_pos.into();
                 let finishing =
                     !shape.is_empty() && shape.vertices[0].dist_squared(pos) < OBSTACLE_PLACING_FINISH_DIST_SQUARED;
@@ -154,13 +169,23 @@ impl State {
                     if !can_add_vertex_to_obstacle(shape.vertices[0], shape) {
                         return;
                     }
-                    if let Some(Placing::Obstacle(shape)) = self.placing.replace(Placing::Obstacle(Shape::new_empty()))
-                    {
-                        self.obstacles.push(shape);
-                        self.obstacles_updated();
-                    } else {
-                        unreachable!();
+                    match self.placing.take() {
+                        Some(Placing::Obstacle(shape)) => {
+                            self.placing = Some(Placing::Obstacle(Shape::new_empty()));
+                            self.obstacles.push(shape.into());
+                        }
+                        Some(Placing::Cut(shape)) => {
+                            self.placing = Some(Placing::Cut(Shape::new_empty()));
+                            let cut = shape.into();
+                            self.obstacles = self
+                                .obstacles
+                                .iter()
+                                .flat_map(|obstacle| obstacle.difference(&cut))
+                                .collect();
+                        }
+                        _ => unreachable!(),
                     }
+                    self.obstacles_updated();
                 } else {
                     if !can_add_vertex_to_obstacle(pos, shape) {
                         return;
@@ -171,12 +196,12 @@ impl State {
             Some(Placing::Start) => {
                 self.placing.take();
                 self.start = Some(mouse_pos.into());
-                self.endpoint_updated();
+                self.start_updated();
             }
             Some(Placing::End) => {
                 self.placing.take();
                 self.end = Some(mouse_pos.into());
-                self.endpoint_updated();
+                self.end_updated();
             }
             _ => {}
         }
@@ -184,12 +209,15 @@ impl State {
     fn render_obstacles(&self, canvas: &Canvas) {
         for obstacle in &self.obstacles {
             canvas.begin_path();
-            if obstacle.is_empty() {
-                continue;
-            }
-            canvas.move_to(*obstacle.vertices.last().unwrap());
-            for vertex in &obstacle.vertices {
-                canvas.line_to(*vertex);
+            // Holes are in the opposite winding order, so they are left out when filling
+            for ring in obstacle.rings() {
+                if ring.is_empty() {
+                    continue;
+                }
+                canvas.move_to(*ring.vertices.last().unwrap());
+                for vertex in &ring.vertices {
+                    canvas.line_to(*vertex);
+                }
             }
             canvas.set_fill_style("#CCC");
             canvas.fill();
@@ -212,7 +240,7 @@ impl State {
         }
     }
     fn render_placing_obstacle(&self, canvas: &Canvas, input: &Input) {
-        if let Some(Placing::Obstacle(shape)) = &self.placing {
+        if let Some(Placing::Obstacle(shape)) | Some(Placing::Cut(shape)) = &self.placing {
             if !shape.is_empty() {
                 canvas.begin_path();
                 canvas.move_to(shape.vertices[0]);
@@ -280,6 +308,7 @@ Controls
 S - Place/move starting point
 E - Place/move ending point
 O - Place obstacles
+C - Cut holes into obstacles
 N - Show/hide navigation graph
 A - Show/hide expanded obstacles
 [, ] - Change reserved path width ({})",
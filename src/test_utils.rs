@@ -0,0 +1,103 @@
+//! Fixtures shared by the tests of the path finders.
+
+use crate::geometry::{orient2d, Segment, Shape, Sign, Vec2};
+use crate::navigation::NavigationObstacle;
+
+/// An axis-aligned square with its lower left corner at `(x, y)`.
+pub(crate) fn square(x: f64, y: f64, size: f64) -> NavigationObstacle {
+    NavigationObstacle::new(vec![
+        Vec2::new(x, y),
+        Vec2::new(x + size, y),
+        Vec2::new(x + size, y + size),
+        Vec2::new(x, y + size),
+    ])
+}
+
+/// `count` by `count` squares, the one in column `i` and row `j` of `size(i, j)` and with its lower
+/// left corner at `origin + (i * spacing + j * shear.x, j * spacing + i * shear.y)`.
+pub(crate) fn square_grid(
+    origin: Vec2,
+    count: usize,
+    spacing: f64,
+    shear: Vec2,
+    size: impl Fn(usize, usize) -> f64,
+) -> Vec<NavigationObstacle> {
+    let mut squares = vec![];
+    for i in 0..count {
+        for j in 0..count {
+            squares.push(square(
+                origin.x + i as f64 * spacing + j as f64 * shear.x,
+                origin.y + j as f64 * spacing + i as f64 * shear.y,
+                size(i, j),
+            ));
+        }
+    }
+    squares
+}
+
+/// Whether the segments cross at a single point inside of both. Touching or overlapping segments
+/// do not count, so that paths may run along edges and through vertices.
+pub(crate) fn properly_crosses(segment: Segment, other: Segment) -> bool {
+    let (a, b) = (segment.p0, segment.p1);
+    orient2d(a, b, other.p0) == -orient2d(a, b, other.p1)
+        && orient2d(other.p0, other.p1, a) == -orient2d(other.p0, other.p1, b)
+        && orient2d(a, b, other.p0) != Sign::Zero
+        && orient2d(other.p0, other.p1, a) != Sign::Zero
+}
+
+/// Whether a segment of `path` properly crosses an edge of `shapes`, see `properly_crosses`.
+pub(crate) fn path_crosses<'a>(path: &[Vec2], shapes: impl IntoIterator<Item = &'a Shape>) -> bool {
+    let edges: Vec<Segment> = shapes.into_iter().flat_map(Shape::segments).collect();
+    path.windows(2).any(|points| {
+        let segment = Segment::new(points[0], points[1]);
+        edges.iter().any(|&edge| properly_crosses(segment, edge))
+    })
+}
+
+/// A linear congruential generator, so that randomized tests are repeatable.
+pub(crate) struct Random(pub(crate) u64);
+
+impl Random {
+    pub(crate) fn next(&mut self) -> f64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+    pub(crate) fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + self.next() * (max - min)
+    }
+    pub(crate) fn point(&mut self, size: f64) -> Vec2 {
+        Vec2::new(self.range(0., size), self.range(0., size))
+    }
+}
+
+/// `count` convex polygons within a square of `size`, which do not overlap.
+pub(crate) fn scattered_polygons(random: &mut Random, count: usize, size: f64) -> Vec<NavigationObstacle> {
+    let mut circles: Vec<(Vec2, f64)> = vec![];
+    while circles.len() < count {
+        let (center, radius) = (random.point(size), random.range(size / 40., size / 10.));
+        if circles
+            .iter()
+            .all(|&(other_center, other_radius)| center.dist(other_center) > radius + other_radius + 1.)
+        {
+            circles.push((center, radius));
+        }
+    }
+    circles
+        .into_iter()
+        .map(|(center, radius)| {
+            let sides = 3 + (random.next() * 6.) as usize;
+            let rotation = random.range(0., 7.);
+            NavigationObstacle::new(
+                (0..sides)
+                    .map(|i| {
+                        let angle = rotation + i as f64 / sides as f64 * std::f64::consts::PI * 2.;
+                        center + Vec2::new(angle.cos(), angle.sin()) * radius
+                    })
+                    .collect(),
+            )
+        })
+        .collect()
+}